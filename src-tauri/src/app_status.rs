@@ -1,8 +1,12 @@
 use std::sync::Mutex;
 
+use crate::diagnostics::clock::ClockSkewReport;
+
 pub struct AppData {
     pub log_buffer: Mutex<Vec<String>>,
     pub error_log_buffer: Mutex<Vec<String>>,
+    /// 最近一次时钟偏差检测结果
+    pub clock_skew: Mutex<Option<ClockSkewReport>>,
 }
 
 pub enum LogType {
@@ -15,6 +19,7 @@ impl AppData {
         Self {
             log_buffer: Mutex::new(Vec::new()),
             error_log_buffer: Mutex::new(Vec::new()),
+            clock_skew: Mutex::new(None),
         }
     }
 
@@ -32,6 +37,12 @@ impl AppData {
         }
     }
 
+    pub fn set_clock_skew(&self, report: ClockSkewReport) {
+        if let Ok(mut clock_skew) = self.clock_skew.lock() {
+            *clock_skew = Some(report);
+        }
+    }
+
     pub fn read(&self, log_type: LogType) -> String {
         let buffer = match log_type {
             LogType::Info => &self.log_buffer,
@@ -55,3 +66,9 @@ pub fn read_logs(app_data: tauri::State<AppData>, is_error: bool) -> String {
     };
     app_data.read(log_type)
 }
+
+/// 最近一次时钟偏差检测结果，exceeded 为 true 时需要提示用户校准时间
+#[tauri::command]
+pub fn get_clock_skew_status(app_data: tauri::State<AppData>) -> Option<ClockSkewReport> {
+    app_data.clock_skew.lock().ok().and_then(|c| c.clone())
+}
@@ -0,0 +1,38 @@
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+pub mod probe;
+pub mod resume;
+
+pub use probe::{CaptiveResult, CaptiveStatus, Probe};
+
+// 用户自定义的检测地址列表，为空时使用默认列表
+const CAPTIVE_PROBES_STORE_KEY: &str = "captive_probes_key";
+
+/// 读取检测地址列表
+pub fn probes(app: &AppHandle) -> Vec<Probe> {
+    app.store("settings.json")
+        .ok()
+        .and_then(|store| store.get(CAPTIVE_PROBES_STORE_KEY))
+        .and_then(|value| serde_json::from_value::<Vec<Probe>>(value).ok())
+        .filter(|probes| !probes.is_empty())
+        .unwrap_or_else(probe::default_probes)
+}
+
+/// 使用配置的检测地址检测认证网络
+pub async fn detect(app: &AppHandle) -> CaptiveResult {
+    let result = probe::detect(&probes(app)).await;
+    log::info!(
+        "Captive portal detection: {:?} via {:?} ({:?} ms)",
+        result.status,
+        result.probe,
+        result.latency_ms
+    );
+    result
+}
+
+/// 检测认证网络，返回详细结果
+#[tauri::command]
+pub async fn detect_captive_portal(app: AppHandle) -> CaptiveResult {
+    detect(&app).await
+}
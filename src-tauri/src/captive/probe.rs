@@ -0,0 +1,427 @@
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tauri_plugin_http::reqwest::{self, header::LOCATION, redirect::Policy};
+use tokio::task::JoinSet;
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+// 只读取响应体开头，足够判断内容与提取跳转地址
+const MAX_BODY_LEN: usize = 16 * 1024;
+
+/// 认证网络检测地址及其预期响应
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Probe {
+    pub url: String,
+    /// 预期状态码，为空时要求 2xx
+    #[serde(default)]
+    pub expected_status: Option<u16>,
+    /// 预期响应体需包含的内容，为空时不检查
+    #[serde(default)]
+    pub expected_body: Option<String>,
+}
+
+impl Probe {
+    fn new(url: &str, expected_status: Option<u16>, expected_body: Option<&str>) -> Self {
+        Self {
+            url: url.to_string(),
+            expected_status,
+            expected_body: expected_body.map(|s| s.to_string()),
+        }
+    }
+}
+
+/// 默认检测地址，均支持无重定向的 http 访问
+pub fn default_probes() -> Vec<Probe> {
+    vec![
+        Probe::new(
+            "http://captive.apple.com/hotspot-detect.html",
+            Some(200),
+            Some("Success"),
+        ),
+        Probe::new(
+            "http://connectivitycheck.gstatic.com/generate_204",
+            Some(204),
+            None,
+        ),
+        Probe::new(
+            "http://www.msftconnecttest.com/connecttest.txt",
+            Some(200),
+            Some("Microsoft Connect Test"),
+        ),
+        Probe::new(
+            "http://nmcheck.gnome.org/check_network_status.txt",
+            Some(200),
+            Some("NetworkManager is online"),
+        ),
+    ]
+}
+
+/// 检测结论
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptiveStatus {
+    /// 可以正常上网
+    Online,
+    /// 需要认证
+    Portal,
+    /// 所有检测地址均无法访问
+    Offline,
+}
+
+/// 单个检测地址的结果
+#[derive(Clone, Debug, Serialize)]
+pub struct ProbeResult {
+    pub url: String,
+    pub status: CaptiveStatus,
+    pub status_code: Option<u16>,
+    pub latency_ms: u64,
+    pub portal_url: Option<String>,
+    pub error: Option<String>,
+}
+
+/// 综合所有检测地址后的结果
+#[derive(Clone, Debug, Serialize)]
+pub struct CaptiveResult {
+    pub status: CaptiveStatus,
+    /// 认证页面地址
+    pub portal_url: Option<String>,
+    /// 做出结论所依据的检测地址（与结论一致且最快的）
+    pub probe: Option<String>,
+    pub latency_ms: Option<u64>,
+    pub probes: Vec<ProbeResult>,
+}
+
+fn build_client() -> reqwest::Client {
+    reqwest::ClientBuilder::new()
+        .timeout(PROBE_TIMEOUT)
+        .redirect(Policy::none())
+        .no_proxy()
+        .build()
+        .unwrap()
+}
+
+/// 从被注入的页面中提取跳转地址（meta refresh 或 location 赋值）
+fn extract_portal_url(body: &str) -> Option<String> {
+    let lower = body.to_ascii_lowercase();
+    let start = [
+        "url=",
+        "location.href=\"",
+        "location.href = \"",
+        "location=\"",
+    ]
+    .iter()
+    .find_map(|marker| lower.find(marker).map(|i| i + marker.len()))?;
+    let rest = body[start..].trim_start_matches(['\'', '"']);
+    let end = rest.find(['"', '\'', '>', ' ', ';']).unwrap_or(rest.len());
+    let url = rest[..end].trim();
+    (url.starts_with("http://") || url.starts_with("https://")).then(|| url.to_string())
+}
+
+async fn run_probe(client: reqwest::Client, probe: Probe) -> ProbeResult {
+    let started = Instant::now();
+    let mut result = ProbeResult {
+        url: probe.url.clone(),
+        status: CaptiveStatus::Offline,
+        status_code: None,
+        latency_ms: 0,
+        portal_url: None,
+        error: None,
+    };
+
+    let response = match client.get(&probe.url).send().await {
+        Ok(response) => response,
+        Err(e) => {
+            result.latency_ms = started.elapsed().as_millis() as u64;
+            result.error = Some(e.to_string());
+            return result;
+        }
+    };
+    let status = response.status();
+    result.status_code = Some(status.as_u16());
+
+    if status.is_redirection() {
+        result.latency_ms = started.elapsed().as_millis() as u64;
+        result.status = CaptiveStatus::Portal;
+        result.portal_url = response
+            .headers()
+            .get(LOCATION)
+            .and_then(|h| h.to_str().ok())
+            .map(|s| s.to_string());
+        return result;
+    }
+
+    let body = read_body(response).await;
+    result.latency_ms = started.elapsed().as_millis() as u64;
+    let body = body.as_str();
+
+    let status_ok = match probe.expected_status {
+        Some(expected) => status.as_u16() == expected,
+        None => status.is_success(),
+    };
+    let body_ok = probe
+        .expected_body
+        .as_ref()
+        .is_none_or(|expected| body.contains(expected.as_str()));
+
+    if status_ok && body_ok {
+        result.status = CaptiveStatus::Online;
+    } else {
+        // 返回 200 但内容被替换，通常是认证页面
+        result.status = CaptiveStatus::Portal;
+        result.portal_url = extract_portal_url(body).or_else(|| Some(probe.url.clone()));
+    }
+    result
+}
+
+/// 分块读取响应体，最多 MAX_BODY_LEN 字节，不等待剩余内容
+async fn read_body(mut response: reqwest::Response) -> String {
+    let mut buf = Vec::new();
+    while buf.len() < MAX_BODY_LEN {
+        match response.chunk().await {
+            Ok(Some(chunk)) => buf.extend_from_slice(&chunk),
+            _ => break,
+        }
+    }
+    buf.truncate(MAX_BODY_LEN);
+    // 截断处可能落在多字节字符中间
+    String::from_utf8_lossy(&buf).into_owned()
+}
+
+/// 多数表决：能访问的检测地址中，认证多于或等于在线时判定为需要认证
+fn decide(results: &[ProbeResult]) -> CaptiveStatus {
+    let online = results
+        .iter()
+        .filter(|r| r.status == CaptiveStatus::Online)
+        .count();
+    let portal = results
+        .iter()
+        .filter(|r| r.status == CaptiveStatus::Portal)
+        .count();
+    if online == 0 && portal == 0 {
+        CaptiveStatus::Offline
+    } else if portal >= online {
+        CaptiveStatus::Portal
+    } else {
+        CaptiveStatus::Online
+    }
+}
+
+/// 并发访问所有检测地址并汇总结果
+pub async fn detect(probes: &[Probe]) -> CaptiveResult {
+    let client = build_client();
+    let mut set = JoinSet::new();
+    for (index, probe) in probes.iter().cloned().enumerate() {
+        let client = client.clone();
+        set.spawn(async move { (index, run_probe(client, probe).await) });
+    }
+    let mut indexed = Vec::with_capacity(probes.len());
+    while let Some(joined) = set.join_next().await {
+        if let Ok(item) = joined {
+            indexed.push(item);
+        }
+    }
+    indexed.sort_by_key(|(index, _)| *index);
+    let results: Vec<ProbeResult> = indexed.into_iter().map(|(_, r)| r).collect();
+
+    let status = decide(&results);
+    let deciding = results
+        .iter()
+        .filter(|r| r.status == status && status != CaptiveStatus::Offline)
+        .min_by_key(|r| r.latency_ms);
+    // 优先使用服务器明确给出的认证地址
+    let portal_url = (status == CaptiveStatus::Portal)
+        .then(|| {
+            results
+                .iter()
+                .filter(|r| r.status == CaptiveStatus::Portal)
+                .filter_map(|r| r.portal_url.clone())
+                .find(|url| !probes.iter().any(|p| p.url == *url))
+                .or_else(|| deciding.and_then(|r| r.portal_url.clone()))
+        })
+        .flatten();
+
+    CaptiveResult {
+        status,
+        portal_url,
+        probe: deciding.map(|r| r.url.clone()),
+        latency_ms: deciding.map(|r| r.latency_ms),
+        probes: results,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// 启动只返回固定响应的本地 HTTP 服务，返回其地址
+    async fn mock_server(status: &str, headers: &str, body: &str) -> String {
+        let response = format!(
+            "HTTP/1.1 {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            headers,
+            body.len(),
+            body
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut stream, _)) = listener.accept().await else {
+                    return;
+                };
+                let response = response.clone();
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 1024];
+                    let _ = stream.read(&mut buf).await;
+                    let _ = stream.write_all(response.as_bytes()).await;
+                    let _ = stream.shutdown().await;
+                });
+            }
+        });
+        format!("http://{}/", addr)
+    }
+
+    async fn no_content() -> String {
+        mock_server("204 No Content", "", "").await
+    }
+
+    async fn success() -> String {
+        mock_server("200 OK", "", "Success").await
+    }
+
+    async fn redirect() -> String {
+        mock_server("302 Found", "Location: http://portal.example/login\r\n", "").await
+    }
+
+    async fn injected() -> String {
+        mock_server(
+            "200 OK",
+            "",
+            "<html><meta http-equiv=\"refresh\" content=\"0;url=http://10.0.0.1/auth\"></html>",
+        )
+        .await
+    }
+
+    /// 声明超大响应体，只发送开头部分后保持连接不关闭
+    async fn endless_portal() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let Ok((mut stream, _)) = listener.accept().await else {
+                return;
+            };
+            let mut buf = [0u8; 1024];
+            let _ = stream.read(&mut buf).await;
+            let mut body =
+                "<meta http-equiv=\"refresh\" content=\"0;url=http://10.0.0.1/auth\">".to_string();
+            body.push_str(&" ".repeat(MAX_BODY_LEN * 2));
+            let head = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n",
+                1024 * 1024 * 1024
+            );
+            let _ = stream.write_all(head.as_bytes()).await;
+            let _ = stream.write_all(body.as_bytes()).await;
+            tokio::time::sleep(Duration::from_secs(30)).await;
+        });
+        format!("http://{}/", addr)
+    }
+
+    fn unreachable() -> String {
+        // 绑定后立即释放的端口，连接会被拒绝
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        format!("http://{}/", addr)
+    }
+
+    #[tokio::test]
+    async fn online_when_probes_match() {
+        let probes = vec![
+            Probe::new(&no_content().await, Some(204), None),
+            Probe::new(&success().await, Some(200), Some("Success")),
+        ];
+        let result = detect(&probes).await;
+        assert_eq!(result.status, CaptiveStatus::Online);
+        assert!(result.portal_url.is_none());
+        assert!(result.probe.is_some());
+        assert!(result.latency_ms.is_some());
+    }
+
+    #[tokio::test]
+    async fn portal_on_redirect() {
+        let probes = vec![Probe::new(&redirect().await, Some(204), None)];
+        let result = detect(&probes).await;
+        assert_eq!(result.status, CaptiveStatus::Portal);
+        assert_eq!(
+            result.portal_url.as_deref(),
+            Some("http://portal.example/login")
+        );
+        assert_eq!(result.probe.as_deref(), Some(probes[0].url.as_str()));
+    }
+
+    #[tokio::test]
+    async fn portal_on_injected_body() {
+        let probes = vec![Probe::new(&injected().await, Some(200), Some("Success"))];
+        let result = detect(&probes).await;
+        assert_eq!(result.status, CaptiveStatus::Portal);
+        assert_eq!(result.portal_url.as_deref(), Some("http://10.0.0.1/auth"));
+    }
+
+    #[tokio::test]
+    async fn reads_only_start_of_large_body() {
+        let probe = Probe::new(&endless_portal().await, Some(200), Some("Success"));
+        let result = run_probe(build_client(), probe).await;
+        assert_eq!(result.status, CaptiveStatus::Portal);
+        assert_eq!(result.portal_url.as_deref(), Some("http://10.0.0.1/auth"));
+        assert!(result.latency_ms < PROBE_TIMEOUT.as_millis() as u64);
+    }
+
+    #[tokio::test]
+    async fn majority_decides() {
+        let probes = vec![
+            Probe::new(&no_content().await, Some(204), None),
+            Probe::new(&success().await, Some(200), Some("Success")),
+            Probe::new(&redirect().await, Some(204), None),
+            Probe::new(&unreachable(), Some(204), None),
+        ];
+        let result = detect(&probes).await;
+        assert_eq!(result.status, CaptiveStatus::Online);
+        assert_eq!(result.probes.len(), 4);
+        assert_eq!(result.probes[3].status, CaptiveStatus::Offline);
+        assert!(result.probes[3].error.is_some());
+    }
+
+    #[tokio::test]
+    async fn blocked_probe_does_not_hide_portal() {
+        let probes = vec![
+            Probe::new(&unreachable(), Some(204), None),
+            Probe::new(&redirect().await, Some(204), None),
+        ];
+        let result = detect(&probes).await;
+        assert_eq!(result.status, CaptiveStatus::Portal);
+    }
+
+    #[tokio::test]
+    async fn offline_when_all_unreachable() {
+        let probes = vec![
+            Probe::new(&unreachable(), Some(204), None),
+            Probe::new(&unreachable(), Some(200), Some("Success")),
+        ];
+        let result = detect(&probes).await;
+        assert_eq!(result.status, CaptiveStatus::Offline);
+        assert!(result.probe.is_none());
+        assert!(result.portal_url.is_none());
+    }
+
+    #[test]
+    fn extracts_portal_url_from_html() {
+        assert_eq!(
+            extract_portal_url(
+                "<script>window.location.href = \"https://wifi.example/a?b=1\";</script>"
+            ),
+            Some("https://wifi.example/a?b=1".to_string())
+        );
+        assert_eq!(extract_portal_url("<html>hello</html>"), None);
+    }
+}
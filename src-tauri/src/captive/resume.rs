@@ -0,0 +1,129 @@
+use lazy_static::lazy_static;
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_store::StoreExt;
+use tokio::task::JoinHandle;
+
+use crate::captive::{self, CaptiveStatus};
+use crate::core::{self, ProxyMode};
+
+// 等待认证完成的最长时间（秒）
+const RESUME_TIMEOUT_STORE_KEY: &str = "captive_resume_timeout_key";
+const DEFAULT_RESUME_TIMEOUT: u64 = 300;
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// captive-resume 事件内容
+#[derive(Clone, Debug, Serialize)]
+pub struct ResumeEvent {
+    /// waiting / resumed / timeout / cancelled / failed
+    pub state: String,
+    pub mode: ProxyMode,
+    pub elapsed_secs: u64,
+    pub error: Option<String>,
+}
+
+lazy_static! {
+    static ref RESUME_TASK: Mutex<Option<JoinHandle<()>>> = Mutex::new(None);
+}
+
+fn resume_timeout(app: &AppHandle) -> Duration {
+    let secs = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get(RESUME_TIMEOUT_STORE_KEY))
+        .and_then(|value| value.as_u64())
+        .unwrap_or(DEFAULT_RESUME_TIMEOUT);
+    Duration::from_secs(secs)
+}
+
+fn emit(app: &AppHandle, state: &str, mode: &ProxyMode, started: Instant, error: Option<String>) {
+    let event = ResumeEvent {
+        state: state.to_string(),
+        mode: mode.clone(),
+        elapsed_secs: started.elapsed().as_secs(),
+        error,
+    };
+    if let Err(e) = app.emit("captive-resume", event) {
+        log::error!("Failed to emit captive-resume event: {}", e);
+    }
+}
+
+async fn wait_and_resume(app: AppHandle, mode: ProxyMode, path: String) {
+    let started = Instant::now();
+    let timeout = resume_timeout(&app);
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+
+        // 用户已手动启动，不再自动恢复
+        if core::current_session().is_some() {
+            log::info!("[captive] Proxy started manually, stop waiting for portal login");
+            emit(&app, "cancelled", &mode, started, None);
+            break;
+        }
+
+        let result = captive::detect(&app).await;
+        if result.status == CaptiveStatus::Online {
+            log::info!(
+                "[captive] Portal login finished after {}s, restarting in {:?}",
+                started.elapsed().as_secs(),
+                mode
+            );
+            match core::start(app.clone(), path.clone(), mode.clone()).await {
+                Ok(_) => emit(&app, "resumed", &mode, started, None),
+                Err(e) => {
+                    log::error!("[captive] Failed to resume proxy: {}", e);
+                    emit(&app, "failed", &mode, started, Some(e));
+                }
+            }
+            break;
+        }
+
+        if started.elapsed() >= timeout {
+            log::warn!(
+                "[captive] Portal login not finished within {}s, give up resuming",
+                timeout.as_secs()
+            );
+            emit(&app, "timeout", &mode, started, None);
+            break;
+        }
+        emit(&app, "waiting", &mode, started, None);
+    }
+    RESUME_TASK.lock().unwrap_or_else(|e| e.into_inner()).take();
+}
+
+/// 因认证网络停止内核后，在后台等待认证完成并以原来的模式恢复
+pub fn start(app: &AppHandle, mode: ProxyMode, path: String) {
+    cancel();
+    let handle = tokio::spawn(wait_and_resume(app.clone(), mode, path));
+    *RESUME_TASK.lock().unwrap_or_else(|e| e.into_inner()) = Some(handle);
+    log::info!("[captive] Waiting for portal login to resume proxy");
+}
+
+/// 取消等待，返回是否有正在等待的任务
+pub fn cancel() -> bool {
+    match RESUME_TASK.lock().unwrap_or_else(|e| e.into_inner()).take() {
+        Some(handle) => {
+            handle.abort();
+            log::info!("[captive] Auto resume cancelled");
+            true
+        }
+        None => false,
+    }
+}
+
+/// 取消认证完成后的自动恢复
+#[tauri::command]
+pub fn cancel_captive_resume() -> bool {
+    cancel()
+}
+
+/// 是否正在等待认证完成后自动恢复
+#[tauri::command]
+pub fn is_captive_resume_pending() -> bool {
+    RESUME_TASK
+        .lock()
+        .map(|task| task.is_some())
+        .unwrap_or(false)
+}
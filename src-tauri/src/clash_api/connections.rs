@@ -0,0 +1,296 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::models::Connection;
+use super::ClashApi;
+
+/// 连接过滤条件，按字段做不区分大小写的包含匹配，填写的条件需要全部满足
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConnectionFilter {
+    /// 匹配任意字段
+    pub keyword: Option<String>,
+    pub host: Option<String>,
+    pub destination_ip: Option<String>,
+    pub destination_port: Option<String>,
+    pub source_ip: Option<String>,
+    pub source_port: Option<String>,
+    /// tcp / udp
+    pub network: Option<String>,
+    pub inbound: Option<String>,
+    pub rule: Option<String>,
+    /// 匹配出站链中的任意一项
+    pub chain: Option<String>,
+    /// 匹配最终出站，即出站链的第一项
+    pub outbound: Option<String>,
+    pub process: Option<String>,
+}
+
+/// 展开后的连接信息
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ConnectionInfo {
+    pub id: String,
+    pub host: String,
+    pub destination_ip: String,
+    pub destination_port: String,
+    pub source_ip: String,
+    pub source_port: String,
+    pub network: String,
+    pub inbound: String,
+    pub rule: String,
+    pub rule_payload: String,
+    /// 出站链，从最终节点到策略组
+    pub chains: Vec<String>,
+    pub process: String,
+    pub upload: u64,
+    pub download: u64,
+    pub start: String,
+}
+
+impl From<Connection> for ConnectionInfo {
+    fn from(c: Connection) -> Self {
+        Self {
+            id: c.id,
+            host: c.metadata.host,
+            destination_ip: c.metadata.destination_ip,
+            destination_port: c.metadata.destination_port,
+            source_ip: c.metadata.source_ip,
+            source_port: c.metadata.source_port,
+            network: c.metadata.network,
+            inbound: c.metadata.inbound,
+            rule: c.rule,
+            rule_payload: c.rule_payload,
+            chains: c.chains,
+            process: c.metadata.process_path,
+            upload: c.upload,
+            download: c.download,
+            start: c.start,
+        }
+    }
+}
+
+fn contains(value: &str, pattern: &str) -> bool {
+    value.to_lowercase().contains(&pattern.to_lowercase())
+}
+
+impl ConnectionFilter {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn matches(&self, c: &ConnectionInfo) -> bool {
+        let field = |pattern: &Option<String>, value: &str| {
+            pattern.as_deref().is_none_or(|p| contains(value, p))
+        };
+        let chain = self
+            .chain
+            .as_deref()
+            .is_none_or(|p| c.chains.iter().any(|name| contains(name, p)));
+        let outbound = self
+            .outbound
+            .as_deref()
+            .is_none_or(|p| c.chains.first().is_some_and(|name| contains(name, p)));
+        let keyword = self.keyword.as_deref().is_none_or(|p| {
+            [
+                &c.id,
+                &c.host,
+                &c.destination_ip,
+                &c.destination_port,
+                &c.source_ip,
+                &c.source_port,
+                &c.network,
+                &c.inbound,
+                &c.rule,
+                &c.rule_payload,
+                &c.process,
+            ]
+            .iter()
+            .any(|value| contains(value, p))
+                || c.chains.iter().any(|name| contains(name, p))
+        });
+        keyword
+            && chain
+            && outbound
+            && field(&self.host, &c.host)
+            && field(&self.destination_ip, &c.destination_ip)
+            && field(&self.destination_port, &c.destination_port)
+            && field(&self.source_ip, &c.source_ip)
+            && field(&self.source_port, &c.source_port)
+            && field(&self.network, &c.network)
+            && field(&self.inbound, &c.inbound)
+            && field(&self.rule, &c.rule)
+            && field(&self.process, &c.process)
+    }
+}
+
+impl ClashApi {
+    /// 符合条件的活动连接
+    pub async fn list_connections(&self, filter: &ConnectionFilter) -> Result<Vec<ConnectionInfo>> {
+        Ok(self
+            .connections()
+            .await?
+            .connections
+            .into_iter()
+            .map(ConnectionInfo::from)
+            .filter(|c| filter.matches(c))
+            .collect())
+    }
+
+    /// 关闭符合条件的连接，返回关闭的连接 id
+    pub async fn close_connections(&self, filter: &ConnectionFilter) -> Result<Vec<String>> {
+        let matched = self.list_connections(filter).await?;
+        if filter.is_empty() {
+            self.close_all_connections().await?;
+            return Ok(matched.into_iter().map(|c| c.id).collect());
+        }
+        let mut closed = Vec::new();
+        for c in matched {
+            // 连接可能已自行结束，单个失败不影响其他连接
+            match self.close_connection(&c.id).await {
+                Ok(()) => closed.push(c.id),
+                Err(e) => log::warn!("[connections] Failed to close {}: {}", c.id, e),
+            }
+        }
+        Ok(closed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{HttpServer, Response};
+
+    const CONNECTIONS: &str = r#"{
+        "downloadTotal": 3000, "uploadTotal": 300, "memory": 0,
+        "connections": [
+            {"id": "c1", "upload": 100, "download": 1000, "start": "2026-01-01T00:00:00Z",
+             "chains": ["HK 01", "ExitGateway"], "rule": "final", "rulePayload": "",
+             "metadata": {"network": "tcp", "type": "mixed/mixed-in", "sourceIP": "127.0.0.1",
+                          "destinationIP": "142.250.1.1", "sourcePort": "50001", "destinationPort": "443",
+                          "host": "www.google.com", "dnsMode": "normal", "processPath": "/usr/bin/firefox"}},
+            {"id": "c2", "upload": 100, "download": 1000, "start": "2026-01-01T00:00:00Z",
+             "chains": ["direct"], "rule": "rule_set=geosite-cn", "rulePayload": "",
+             "metadata": {"network": "tcp", "type": "tun/tun-in", "sourceIP": "172.19.0.1",
+                          "destinationIP": "110.242.68.66", "sourcePort": "50002", "destinationPort": "443",
+                          "host": "www.baidu.com", "dnsMode": "normal", "processPath": "/usr/bin/curl"}},
+            {"id": "c3", "upload": 100, "download": 1000, "start": "2026-01-01T00:00:00Z",
+             "chains": ["JP 01", "ExitGateway"], "rule": "final", "rulePayload": "",
+             "metadata": {"network": "udp", "type": "tun/tun-in", "sourceIP": "172.19.0.1",
+                          "destinationIP": "1.1.1.1", "sourcePort": "50003", "destinationPort": "53",
+                          "host": "", "dnsMode": "normal", "processPath": "/usr/bin/firefox"}}
+        ]
+    }"#;
+
+    /// 模拟 Clash API
+    async fn mock_api() -> HttpServer {
+        HttpServer::start(|line| async move {
+            if line.starts_with("GET /connections ") {
+                Response::json("200 OK", CONNECTIONS)
+            } else if line.starts_with("DELETE /connections") {
+                Response::json("204 No Content", "")
+            } else {
+                Response::json("404 Not Found", r#"{"message":"Resource not found"}"#)
+            }
+        })
+        .await
+    }
+
+    fn ids(connections: &[ConnectionInfo]) -> Vec<&str> {
+        connections.iter().map(|c| c.id.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn lists_connections_by_field() {
+        let api = mock_api().await.clash_api("");
+
+        let all = api
+            .list_connections(&ConnectionFilter::default())
+            .await
+            .unwrap();
+        assert_eq!(ids(&all), vec!["c1", "c2", "c3"]);
+        assert_eq!(all[0].host, "www.google.com");
+        assert_eq!(all[0].inbound, "mixed/mixed-in");
+        assert_eq!(all[0].process, "/usr/bin/firefox");
+
+        let filter = ConnectionFilter {
+            chain: Some("exitgateway".to_string()),
+            network: Some("udp".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            ids(&api.list_connections(&filter).await.unwrap()),
+            vec!["c3"]
+        );
+
+        let filter = ConnectionFilter {
+            keyword: Some("geosite".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            ids(&api.list_connections(&filter).await.unwrap()),
+            vec!["c2"]
+        );
+
+        let filter = ConnectionFilter {
+            destination_port: Some("443".to_string()),
+            outbound: Some("hk".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            ids(&api.list_connections(&filter).await.unwrap()),
+            vec!["c1"]
+        );
+
+        // 出站只匹配链的第一项，策略组名称不算
+        let filter = ConnectionFilter {
+            outbound: Some("ExitGateway".to_string()),
+            ..Default::default()
+        };
+        assert!(api.list_connections(&filter).await.unwrap().is_empty());
+
+        let filter = ConnectionFilter {
+            source_port: Some("50003".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            ids(&api.list_connections(&filter).await.unwrap()),
+            vec!["c3"]
+        );
+    }
+
+    #[tokio::test]
+    async fn closes_only_matching_connections() {
+        let server = mock_api().await;
+        let api = server.clash_api("");
+        let filter = ConnectionFilter {
+            process: Some("firefox".to_string()),
+            ..Default::default()
+        };
+        let closed = api.close_connections(&filter).await.unwrap();
+        assert_eq!(closed, vec!["c1", "c3"]);
+
+        let requests = server.requests();
+        let deletes: Vec<_> = requests
+            .iter()
+            .filter(|l| l.starts_with("DELETE"))
+            .collect();
+        assert_eq!(
+            deletes,
+            vec![
+                "DELETE /connections/c1 HTTP/1.1",
+                "DELETE /connections/c3 HTTP/1.1"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn closes_single_connection() {
+        let server = mock_api().await;
+        let api = server.clash_api("");
+        api.close_connection("c2").await.unwrap();
+        assert_eq!(
+            server.requests().last().unwrap(),
+            "DELETE /connections/c2 HTTP/1.1"
+        );
+    }
+}
@@ -0,0 +1,495 @@
+use anyhow::{anyhow, Context, Result};
+use lazy_static::lazy_static;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::Duration;
+use tauri::AppHandle;
+use tauri_plugin_http::reqwest::{self, Method, RequestBuilder, Url};
+use tauri_plugin_store::StoreExt;
+
+use crate::core;
+use crate::selection;
+
+pub mod connections;
+pub mod models;
+
+pub use connections::{ConnectionFilter, ConnectionInfo};
+pub use models::{Configs, Connections, Memory, Proxies, Proxy, Traffic, Version};
+
+// 与前端 CLASH_API_SECRET 一致
+const CLASH_API_SECRET_STORE_KEY: &str = "clash_api_secret_key";
+const DEFAULT_ADDRESS: &str = "127.0.0.1:9191";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+pub const DEFAULT_DELAY_URL: &str = "https://www.google.com/generate_204";
+pub const DEFAULT_DELAY_TIMEOUT_MS: u64 = 5000;
+
+lazy_static! {
+    // 所有请求共用一个连接池，不经过系统代理
+    static ref CLIENT: reqwest::Client = reqwest::ClientBuilder::new()
+        .no_proxy()
+        .build()
+        .unwrap();
+}
+
+/// Clash API 的地址与密钥
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Endpoint {
+    pub address: String,
+    pub secret: String,
+}
+
+impl Endpoint {
+    /// 从正在运行的配置读取 experimental.clash_api，未运行时使用默认地址与保存的密钥
+    pub fn current(app: &AppHandle) -> Self {
+        let running = core::current_session().and_then(|(_, path)| {
+            let content = std::fs::read_to_string(path).ok()?;
+            let config: Value = serde_json::from_str(&content).ok()?;
+            let clash_api = config["experimental"]["clash_api"].clone();
+            Some((
+                clash_api["external_controller"].as_str()?.to_string(),
+                clash_api["secret"].as_str().map(|s| s.to_string()),
+            ))
+        });
+        let (address, secret) = running
+            .map(|(address, secret)| (local_address(&address), secret))
+            .unwrap_or_else(|| (DEFAULT_ADDRESS.to_string(), None));
+        let secret = secret.unwrap_or_else(|| {
+            app.store("settings.json")
+                .ok()
+                .and_then(|store| store.get(CLASH_API_SECRET_STORE_KEY))
+                .and_then(|value| value.as_str().map(|s| s.to_string()))
+                .unwrap_or_default()
+        });
+        Self { address, secret }
+    }
+}
+
+/// external_controller 可以只写端口或监听所有地址，此时通过本机地址访问
+fn local_address(address: &str) -> String {
+    let Some((host, port)) = address.rsplit_once(':') else {
+        return address.to_string();
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    if host.is_empty() || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_unspecified()) {
+        format!("127.0.0.1:{}", port)
+    } else {
+        address.to_string()
+    }
+}
+
+/// Clash API 客户端
+#[derive(Clone, Debug)]
+pub struct ClashApi {
+    endpoint: Endpoint,
+}
+
+impl ClashApi {
+    pub fn new(endpoint: Endpoint) -> Self {
+        Self { endpoint }
+    }
+
+    pub fn current(app: &AppHandle) -> Self {
+        Self::new(Endpoint::current(app))
+    }
+
+    /// 拼接地址，路径中的每一段都会被转义（节点名称可能包含空格、中文等）
+    fn url(&self, segments: &[&str]) -> Result<Url> {
+        let mut url = Url::parse(&format!("http://{}/", self.endpoint.address))
+            .with_context(|| format!("invalid Clash API address {}", self.endpoint.address))?;
+        url.path_segments_mut()
+            .map_err(|_| anyhow!("invalid Clash API address {}", self.endpoint.address))?
+            .pop_if_empty()
+            .extend(segments);
+        Ok(url)
+    }
+
+    fn request(&self, method: Method, segments: &[&str]) -> Result<RequestBuilder> {
+        let mut builder = CLIENT
+            .request(method, self.url(segments)?)
+            .timeout(REQUEST_TIMEOUT);
+        if !self.endpoint.secret.is_empty() {
+            builder = builder.header("Authorization", format!("Bearer {}", self.endpoint.secret));
+        }
+        Ok(builder)
+    }
+
+    /// 发送请求，非 2xx 时返回响应中的 message
+    async fn send(&self, builder: RequestBuilder) -> Result<String> {
+        let response = builder.send().await.context("Clash API request failed")?;
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            let message = serde_json::from_str::<Value>(&body)
+                .ok()
+                .and_then(|v| v["message"].as_str().map(|s| s.to_string()))
+                .unwrap_or(body);
+            return Err(anyhow!("Clash API returned {}: {}", status, message.trim()));
+        }
+        Ok(body)
+    }
+
+    async fn get<T: DeserializeOwned>(&self, segments: &[&str]) -> Result<T> {
+        let body = self.send(self.request(Method::GET, segments)?).await?;
+        serde_json::from_str(&body).context("invalid Clash API response")
+    }
+
+    /// 读取流式接口推送的第一条数据
+    async fn first_message<T: DeserializeOwned>(&self, segments: &[&str]) -> Result<T> {
+        let mut response = self
+            .request(Method::GET, segments)?
+            .send()
+            .await
+            .context("Clash API request failed")?;
+        if !response.status().is_success() {
+            return Err(anyhow!("Clash API returned {}", response.status()));
+        }
+        let mut buf = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            buf.extend_from_slice(&chunk);
+            if let Some(end) = buf.iter().position(|b| *b == b'\n') {
+                buf.truncate(end);
+                break;
+            }
+        }
+        serde_json::from_slice(&buf).context("invalid Clash API response")
+    }
+
+    pub async fn version(&self) -> Result<Version> {
+        self.get(&["version"]).await
+    }
+
+    pub async fn proxies(&self) -> Result<HashMap<String, Proxy>> {
+        Ok(self.get::<Proxies>(&["proxies"]).await?.proxies)
+    }
+
+    pub async fn proxy(&self, name: &str) -> Result<Proxy> {
+        self.get(&["proxies", name]).await
+    }
+
+    /// 切换选择器
+    pub async fn select(&self, group: &str, name: &str) -> Result<()> {
+        let builder = self
+            .request(Method::PUT, &["proxies", group])?
+            .header("Content-Type", "application/json")
+            .body(json!({ "name": name }).to_string());
+        self.send(builder).await?;
+        Ok(())
+    }
+
+    /// 沿策略组逐级查找当前实际使用的节点
+    pub async fn resolve_now(&self, group: &str) -> Result<String> {
+        let mut name = group.to_string();
+        // 嵌套层数有限，避免配置异常时死循环
+        for _ in 0..8 {
+            match self.proxy(&name).await?.now {
+                Some(now) if !now.is_empty() => name = now,
+                _ => break,
+            }
+        }
+        Ok(name)
+    }
+
+    /// 测试单个节点的延迟（毫秒）
+    pub async fn delay(&self, name: &str, url: &str, timeout_ms: u64) -> Result<u16> {
+        let builder = self
+            .request(Method::GET, &["proxies", name, "delay"])?
+            .query(&[("url", url), ("timeout", &timeout_ms.to_string())])
+            .timeout(Duration::from_millis(timeout_ms) + REQUEST_TIMEOUT);
+        let body = self.send(builder).await?;
+        Ok(serde_json::from_str::<models::Delay>(&body)?.delay)
+    }
+
+    /// 测试策略组内所有节点的延迟，超时的节点不在结果中
+    pub async fn group_delay(
+        &self,
+        group: &str,
+        url: &str,
+        timeout_ms: u64,
+    ) -> Result<HashMap<String, u16>> {
+        let builder = self
+            .request(Method::GET, &["group", group, "delay"])?
+            .query(&[("url", url), ("timeout", &timeout_ms.to_string())])
+            .timeout(Duration::from_millis(timeout_ms) + REQUEST_TIMEOUT);
+        let body = self.send(builder).await?;
+        serde_json::from_str(&body).context("invalid Clash API response")
+    }
+
+    pub async fn connections(&self) -> Result<Connections> {
+        self.get(&["connections"]).await
+    }
+
+    pub async fn close_connection(&self, id: &str) -> Result<()> {
+        self.send(self.request(Method::DELETE, &["connections", id])?)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn close_all_connections(&self) -> Result<()> {
+        self.send(self.request(Method::DELETE, &["connections"])?)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn traffic(&self) -> Result<Traffic> {
+        self.first_message(&["traffic"]).await
+    }
+
+    pub async fn memory(&self) -> Result<Memory> {
+        self.first_message(&["memory"]).await
+    }
+
+    pub async fn configs(&self) -> Result<Configs> {
+        self.get(&["configs"]).await
+    }
+
+    /// 修改运行中的配置，如 {"mode": "global"}
+    pub async fn patch_configs(&self, patch: &Value) -> Result<()> {
+        let builder = self
+            .request(Method::PATCH, &["configs"])?
+            .header("Content-Type", "application/json")
+            .body(patch.to_string());
+        self.send(builder).await?;
+        Ok(())
+    }
+}
+
+/// 获取 Clash API 的地址与密钥，供前端读取日志等流式接口
+#[tauri::command]
+pub fn get_clash_endpoint(app: AppHandle) -> Endpoint {
+    Endpoint::current(&app)
+}
+
+/// 获取内核版本
+#[tauri::command]
+pub async fn get_clash_version(app: AppHandle) -> Result<Version, String> {
+    ClashApi::current(&app)
+        .version()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 获取所有节点与策略组
+#[tauri::command]
+pub async fn get_clash_proxies(app: AppHandle) -> Result<HashMap<String, Proxy>, String> {
+    ClashApi::current(&app)
+        .proxies()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 获取单个节点或策略组
+#[tauri::command]
+pub async fn get_clash_proxy(app: AppHandle, name: String) -> Result<Proxy, String> {
+    ClashApi::current(&app)
+        .proxy(&name)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 切换选择器，并按订阅记住选择
+#[tauri::command]
+pub async fn select_clash_proxy(app: AppHandle, group: String, name: String) -> Result<(), String> {
+    ClashApi::current(&app)
+        .select(&group, &name)
+        .await
+        .map_err(|e| e.to_string())?;
+    selection::record(&app, &group, &name).await;
+    Ok(())
+}
+
+/// 测试单个节点的延迟
+#[tauri::command]
+pub async fn test_clash_proxy_delay(
+    app: AppHandle,
+    name: String,
+    url: Option<String>,
+    timeout: Option<u64>,
+) -> Result<u16, String> {
+    ClashApi::current(&app)
+        .delay(
+            &name,
+            url.as_deref().unwrap_or(DEFAULT_DELAY_URL),
+            timeout.unwrap_or(DEFAULT_DELAY_TIMEOUT_MS),
+        )
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 测试策略组内所有节点的延迟
+#[tauri::command]
+pub async fn test_clash_group_delay(
+    app: AppHandle,
+    group: String,
+    url: Option<String>,
+    timeout: Option<u64>,
+) -> Result<HashMap<String, u16>, String> {
+    ClashApi::current(&app)
+        .group_delay(
+            &group,
+            url.as_deref().unwrap_or(DEFAULT_DELAY_URL),
+            timeout.unwrap_or(DEFAULT_DELAY_TIMEOUT_MS),
+        )
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 获取活动连接
+#[tauri::command]
+pub async fn get_clash_connections(app: AppHandle) -> Result<Connections, String> {
+    ClashApi::current(&app)
+        .connections()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 关闭单个连接
+#[tauri::command]
+pub async fn close_clash_connection(app: AppHandle, id: String) -> Result<(), String> {
+    ClashApi::current(&app)
+        .close_connection(&id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 按条件列出活动连接
+#[tauri::command]
+pub async fn list_clash_connections(
+    app: AppHandle,
+    filter: Option<ConnectionFilter>,
+) -> Result<Vec<ConnectionInfo>, String> {
+    ClashApi::current(&app)
+        .list_connections(&filter.unwrap_or_default())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 关闭符合条件的连接，返回关闭的连接 id
+#[tauri::command]
+pub async fn close_clash_connections(
+    app: AppHandle,
+    filter: ConnectionFilter,
+) -> Result<Vec<String>, String> {
+    ClashApi::current(&app)
+        .close_connections(&filter)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 关闭所有连接
+#[tauri::command]
+pub async fn close_all_clash_connections(app: AppHandle) -> Result<(), String> {
+    ClashApi::current(&app)
+        .close_all_connections()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 获取当前上传、下载速率
+#[tauri::command]
+pub async fn get_clash_traffic(app: AppHandle) -> Result<Traffic, String> {
+    ClashApi::current(&app)
+        .traffic()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 获取内核内存占用
+#[tauri::command]
+pub async fn get_clash_memory(app: AppHandle) -> Result<Memory, String> {
+    ClashApi::current(&app)
+        .memory()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 获取运行中的配置
+#[tauri::command]
+pub async fn get_clash_configs(app: AppHandle) -> Result<Configs, String> {
+    ClashApi::current(&app)
+        .configs()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 修改运行中的配置
+#[tauri::command]
+pub async fn patch_clash_configs(app: AppHandle, patch: Value) -> Result<(), String> {
+    ClashApi::current(&app)
+        .patch_configs(&patch)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{HttpServer, Response};
+
+    fn api(address: &str) -> ClashApi {
+        ClashApi::new(Endpoint {
+            address: address.to_string(),
+            secret: String::new(),
+        })
+    }
+
+    #[test]
+    fn escapes_each_path_segment() {
+        let api = api("127.0.0.1:9191");
+        assert_eq!(
+            api.url(&["proxies", "HK 01"]).unwrap().as_str(),
+            "http://127.0.0.1:9191/proxies/HK%2001"
+        );
+        assert_eq!(
+            api.url(&["proxies", "a/b", "delay"]).unwrap().as_str(),
+            "http://127.0.0.1:9191/proxies/a%2Fb/delay"
+        );
+        assert_eq!(
+            api.url(&["proxies", "香港 01"]).unwrap().as_str(),
+            "http://127.0.0.1:9191/proxies/%E9%A6%99%E6%B8%AF%2001"
+        );
+    }
+
+    #[test]
+    fn connects_to_loopback_when_listening_on_all_addresses() {
+        assert_eq!(local_address(":9191"), "127.0.0.1:9191");
+        assert_eq!(local_address("0.0.0.0:9191"), "127.0.0.1:9191");
+        assert_eq!(local_address("[::]:9191"), "127.0.0.1:9191");
+        assert_eq!(local_address("127.0.0.1:9191"), "127.0.0.1:9191");
+        assert_eq!(local_address("192.168.1.2:9090"), "192.168.1.2:9090");
+        assert_eq!(local_address("[::1]:9191"), "[::1]:9191");
+    }
+
+    #[tokio::test]
+    async fn returns_message_from_error_response() {
+        let server = HttpServer::start(|line| async move {
+            if line.starts_with("GET /proxies/gone ") {
+                Response::json("404 Not Found", r#"{"message":"Resource not found"}"#)
+            } else {
+                Response::json("502 Bad Gateway", "upstream closed\n")
+            }
+        })
+        .await;
+        let api = server.clash_api("");
+
+        let error = api.proxy("gone").await.unwrap_err().to_string();
+        assert!(error.contains("404"), "{}", error);
+        assert!(error.ends_with("Resource not found"), "{}", error);
+        // 响应不是 JSON 时返回原文
+        let error = api.version().await.unwrap_err().to_string();
+        assert!(error.ends_with("upstream closed"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn reads_first_line_of_stream() {
+        // 第一条数据被拆成两块，第二块同时带有下一条数据的开头
+        let server = HttpServer::start(|_| async move {
+            Response::stream(&[r#"{"up":12,"#, "\"down\":34}\n{\"up\":5", r#","down":6}"#])
+        })
+        .await;
+        let traffic = server.clash_api("").traffic().await.unwrap();
+        assert_eq!((traffic.up, traffic.down), (12, 34));
+    }
+}
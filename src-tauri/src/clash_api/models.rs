@@ -0,0 +1,131 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// GET /version
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Version {
+    pub version: String,
+    pub premium: bool,
+    pub meta: bool,
+}
+
+/// 一次延迟测试记录
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DelayHistory {
+    pub time: String,
+    pub delay: u16,
+}
+
+/// 节点或策略组，策略组才有 now 与 all
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Proxy {
+    pub name: String,
+    /// 如 Selector、URLTest、Direct、Shadowsocks、VMess
+    #[serde(rename = "type")]
+    pub proxy_type: String,
+    pub udp: bool,
+    pub history: Vec<DelayHistory>,
+    pub now: Option<String>,
+    pub all: Option<Vec<String>>,
+}
+
+impl Proxy {
+    /// 可以通过 API 切换的选择器
+    pub fn is_selector(&self) -> bool {
+        self.proxy_type == "Selector"
+    }
+}
+
+/// GET /proxies
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Proxies {
+    pub proxies: HashMap<String, Proxy>,
+}
+
+/// GET /proxies/{name}/delay
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Delay {
+    pub delay: u16,
+}
+
+/// 连接的元数据
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ConnectionMetadata {
+    pub network: String,
+    /// 入站，如 mixed/mixed-in、tun/tun-in
+    #[serde(rename = "type")]
+    pub inbound: String,
+    #[serde(rename = "sourceIP")]
+    pub source_ip: String,
+    #[serde(rename = "destinationIP")]
+    pub destination_ip: String,
+    pub source_port: String,
+    pub destination_port: String,
+    pub host: String,
+    pub dns_mode: String,
+    pub process_path: String,
+}
+
+/// 一条活动连接
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Connection {
+    pub id: String,
+    pub metadata: ConnectionMetadata,
+    pub upload: u64,
+    pub download: u64,
+    pub start: String,
+    /// 出站链，从最终节点到策略组
+    pub chains: Vec<String>,
+    pub rule: String,
+    pub rule_payload: String,
+}
+
+/// GET /connections
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Connections {
+    pub download_total: u64,
+    pub upload_total: u64,
+    pub connections: Vec<Connection>,
+    pub memory: u64,
+}
+
+/// GET /traffic 推送的一条数据（字节/秒）
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Traffic {
+    pub up: u64,
+    pub down: u64,
+}
+
+/// GET /memory 推送的一条数据（字节）
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Memory {
+    pub inuse: u64,
+    pub oslimit: u64,
+}
+
+/// GET /configs
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Configs {
+    pub port: u16,
+    pub socks_port: u16,
+    pub redir_port: u16,
+    pub tproxy_port: u16,
+    pub mixed_port: u16,
+    pub allow_lan: bool,
+    pub bind_address: String,
+    pub mode: String,
+    pub mode_list: Vec<String>,
+    pub log_level: String,
+    pub ipv6: bool,
+}
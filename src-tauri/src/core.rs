@@ -1,13 +1,17 @@
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::path::Path;
 use std::sync::{Arc, Mutex};
 use tauri::Manager;
-use tauri_plugin_http::reqwest;
 
 use crate::app_status::{AppData, LogType};
+use crate::clash_api;
+use crate::pac;
 #[cfg(not(target_os = "windows"))]
 use crate::privilege;
+use crate::selection;
+use crate::vpn::guard;
 use crate::vpn::helper;
 use crate::vpn::{PlatformVpnProxy, VpnProxy};
 use tauri::Emitter;
@@ -20,6 +24,22 @@ pub enum ProxyMode {
     #[default]
     SystemProxy,
     TunProxy,
+    /// 由 OneBox 提供 PAC 文件的自动代理模式
+    Pac,
+    /// TUN 模式，同时将系统代理指向混合入站
+    TunWithSystemProxy,
+}
+
+impl ProxyMode {
+    /// 是否以 TUN 模式（特权）运行内核
+    pub fn is_tun(&self) -> bool {
+        matches!(self, ProxyMode::TunProxy | ProxyMode::TunWithSystemProxy)
+    }
+
+    /// 是否需要设置系统代理
+    pub fn uses_system_proxy(&self) -> bool {
+        matches!(self, ProxyMode::SystemProxy | ProxyMode::TunWithSystemProxy)
+    }
 }
 
 /// 进程管理器，记录当前代理进程及模式
@@ -54,7 +74,7 @@ pub async fn version(app: tauri::AppHandle) -> Result<String, String> {
 async fn get_password_for_mode(mode: &ProxyMode) -> Result<String, String> {
     #[cfg(any(target_os = "linux", target_os = "macos"))]
     {
-        if *mode == ProxyMode::TunProxy {
+        if mode.is_tun() {
             let pwd = privilege::get_privilege_password_from_keyring().await;
             // 如果密码为空，返回特殊错误标识，而不是直接失败
             if pwd.is_empty() {
@@ -94,7 +114,7 @@ pub async fn start(app: tauri::AppHandle, path: String, mode: ProxyMode) -> Resu
     let is_managed_process;
 
     // 准备命令
-    let sidecar_command_opt = if mode == ProxyMode::SystemProxy {
+    let sidecar_command_opt = if !mode.is_tun() {
         // 普通权限执行
         is_managed_process = true;
         match app.shell().sidecar("sing-box") {
@@ -113,7 +133,8 @@ pub async fn start(app: tauri::AppHandle, path: String, mode: ProxyMode) -> Resu
                     sidecar_path,
                     path.clone(),
                     password.clone(),
-                );
+                )
+                .inspect_err(|e| log::error!("Failed to prepare TUN mode: {}", e))?;
                 is_managed_process = cmd.is_some();
                 cmd
             }
@@ -198,12 +219,31 @@ pub async fn start(app: tauri::AppHandle, path: String, mode: ProxyMode) -> Resu
                                         manager.child = None;
                                         manager.current_mode = None;
                                         manager.config_path = None;
-                                        manager.tun_password = None;
+                                        let tun_password = manager.tun_password.take();
+
+                                        // TUN 进程意外退出时恢复 DNS 与转发配置
+                                        #[cfg(target_os = "linux")]
+                                        if let Some(password) = &tun_password {
+                                            if let Err(e) =
+                                                crate::vpn::linux::cleanup_tun_session(password)
+                                            {
+                                                log::error!(
+                                                    "Failed to clean up TUN session: {}",
+                                                    e
+                                                );
+                                            }
+                                        }
+                                        #[cfg(not(target_os = "linux"))]
+                                        let _ = tun_password;
 
-                                        // 如果是系统代理模式，则需要取消系统代理设置
-                                        if process_mode == ProxyMode::SystemProxy {
+                                        // 如果设置过系统代理或 PAC，则需要取消系统代理设置
+                                        if process_mode != ProxyMode::TunProxy {
                                             // 不能在同步代码块中异步调用，所以这里克隆并立即释放锁
                                             drop(manager);
+                                            guard::stop();
+                                            if process_mode == ProxyMode::Pac {
+                                                pac::stop();
+                                            }
 
                                             // 在异步块外清理代理设置
                                             let cleanup_app = app_handle.clone();
@@ -269,19 +309,19 @@ pub async fn start(app: tauri::AppHandle, path: String, mode: ProxyMode) -> Resu
 
         manager.current_mode = Some(mode.clone());
         manager.config_path = Some(path.clone());
-        manager.tun_password = if mode == ProxyMode::TunProxy {
-            Some(password)
-        } else {
-            None
-        };
+        manager.tun_password = if mode.is_tun() { Some(password) } else { None };
         manager.child = child_opt;
     } // MutexGuard 在这里被释放
 
     // 根据模式设置或取消系统代理 (异步操作)
-    let proxy_result = if mode == ProxyMode::SystemProxy {
-        PlatformVpnProxy::set_proxy(&app).await
-    } else {
-        PlatformVpnProxy::unset_proxy(&app).await
+    let proxy_result = match mode {
+        ProxyMode::SystemProxy => PlatformVpnProxy::set_proxy(&app).await,
+        ProxyMode::Pac => match pac::start(&app, &path).await {
+            Ok(url) => PlatformVpnProxy::set_pac_proxy(&app, &url).await,
+            Err(e) => Err(e),
+        },
+        ProxyMode::TunProxy => PlatformVpnProxy::unset_proxy(&app).await,
+        ProxyMode::TunWithSystemProxy => PlatformVpnProxy::set_proxy(&app).await,
     };
 
     // 处理代理设置结果
@@ -292,6 +332,11 @@ pub async fn start(app: tauri::AppHandle, path: String, mode: ProxyMode) -> Resu
         return Err(e.to_string());
     }
 
+    // 设置了系统代理时守护代理设置，防止被其他程序覆盖
+    if mode.uses_system_proxy() {
+        guard::start(&app);
+    }
+
     // 等待进程启动
     let wait_time = if is_managed_process {
         std::time::Duration::from_millis(1500)
@@ -306,6 +351,9 @@ pub async fn start(app: tauri::AppHandle, path: String, mode: ProxyMode) -> Resu
         log::error!("Failed to emit status-changed event: {}", e);
     }
 
+    // 恢复当前订阅下记住的节点选择
+    tauri::async_runtime::spawn(selection::restore(app.clone()));
+
     Ok(())
 }
 
@@ -336,13 +384,19 @@ pub async fn stop(app: tauri::AppHandle) -> Result<(), String> {
         (mode, password, child)
     }; // MutexGuard在此作用域结束时释放
 
+    // 先停止守护任务，避免在恢复系统代理时被重新设置
+    guard::stop();
+
     // 根据当前模式执行清理操作
     if let Some(mode) = &current_mode {
         match mode {
-            ProxyMode::SystemProxy => {
+            ProxyMode::SystemProxy | ProxyMode::Pac => {
                 if let Err(e) = PlatformVpnProxy::unset_proxy(&app).await {
                     log::error!("Failed to unset system proxy: {}", e);
                 }
+                if *mode == ProxyMode::Pac {
+                    pac::stop();
+                }
                 // 停止进程
                 #[cfg(unix)]
                 {
@@ -374,7 +428,13 @@ pub async fn stop(app: tauri::AppHandle) -> Result<(), String> {
                 // 睡眠 0.5 等待进程退出
                 std::thread::sleep(std::time::Duration::from_millis(500));
             }
-            ProxyMode::TunProxy => {
+            ProxyMode::TunProxy | ProxyMode::TunWithSystemProxy => {
+                // 先恢复系统代理，避免应用继续连接即将关闭的混合入站
+                if *mode == ProxyMode::TunWithSystemProxy {
+                    if let Err(e) = PlatformVpnProxy::unset_proxy(&app).await {
+                        log::error!("Failed to unset system proxy: {}", e);
+                    }
+                }
                 if let Some(password) = &tun_password {
                     PlatformVpnProxy::stop_tun_process(password).map_err(|e| {
                         log::error!("Failed to stop TUN process: {}", e);
@@ -392,15 +452,18 @@ pub async fn stop(app: tauri::AppHandle) -> Result<(), String> {
 
 /// 判断代理进程是否运行中
 #[tauri::command]
-pub async fn is_running(secret: String) -> bool {
+pub async fn is_running(app: tauri::AppHandle, secret: String) -> bool {
     use std::time::Duration;
     use tokio::net::TcpStream;
     use tokio::time::timeout;
 
+    let mut endpoint = clash_api::Endpoint::current(&app);
+    endpoint.secret = secret;
+
     // 先快速检查端口是否开放
     if timeout(
         Duration::from_millis(100),
-        TcpStream::connect("127.0.0.1:9191"),
+        TcpStream::connect(&endpoint.address),
     )
     .await
     .is_err()
@@ -408,27 +471,51 @@ pub async fn is_running(secret: String) -> bool {
         return false;
     }
 
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(1))
-        .no_proxy()
-        .build()
-        .unwrap();
-
-    let res = client
-        .get("http://127.0.0.1:9191/version")
-        .header("Authorization", format!("Bearer {}", secret));
-    let res = res.send().await;
-    if let Ok(res) = res {
-        if res.status() == 200 {
-            return true;
+    timeout(
+        Duration::from_secs(1),
+        clash_api::ClashApi::new(endpoint).version(),
+    )
+    .await
+    .is_ok_and(|version| version.is_ok())
+}
+
+/// 当前运行的代理模式与配置文件路径
+pub fn current_session() -> Option<(ProxyMode, String)> {
+    let manager = match PROCESS_MANAGER.lock() {
+        Ok(m) => m,
+        Err(e) => e.into_inner(),
+    };
+    Some((manager.current_mode.clone()?, manager.config_path.clone()?))
+}
+
+/// 运行中配置的出口选择器
+pub fn exit_selector(config_path: &str) -> String {
+    std::fs::read_to_string(config_path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+        .and_then(|config| config["route"]["final"].as_str().map(|s| s.to_string()))
+        .unwrap_or_else(|| "ExitGateway".to_string())
+}
+
+/// 根据当前配置文件重新生成 PAC
+fn refresh_pac(app: &tauri::AppHandle) {
+    let config_path = {
+        let manager = match PROCESS_MANAGER.lock() {
+            Ok(m) => m,
+            Err(e) => e.into_inner(),
+        };
+        manager.config_path.clone()
+    };
+    if let Some(path) = config_path {
+        if let Err(e) = pac::refresh(app, &path) {
+            log::error!("Failed to refresh PAC: {}", e);
         }
     }
-    false
 }
 
 // 重载配置
 #[tauri::command]
-pub async fn reload_config(is_tun: bool) -> Result<String, String> {
+pub async fn reload_config(app: tauri::AppHandle, is_tun: bool) -> Result<String, String> {
     #[cfg(unix)]
     {
         use std::process::Command;
@@ -443,16 +530,27 @@ pub async fn reload_config(is_tun: bool) -> Result<String, String> {
                     Some(ProxyMode::TunProxy),
                     manager.tun_password.clone().unwrap_or_default(),
                 ),
+                Some(ProxyMode::TunWithSystemProxy) if is_tun => (
+                    Some(ProxyMode::TunWithSystemProxy),
+                    manager.tun_password.clone().unwrap_or_default(),
+                ),
                 Some(ProxyMode::SystemProxy) if !is_tun => (
                     Some(ProxyMode::SystemProxy),
                     manager.tun_password.clone().unwrap_or_default(),
                 ),
-                Some(ProxyMode::TunProxy) => {
+                Some(ProxyMode::Pac) if !is_tun => (
+                    Some(ProxyMode::Pac),
+                    manager.tun_password.clone().unwrap_or_default(),
+                ),
+                Some(ProxyMode::TunProxy) | Some(ProxyMode::TunWithSystemProxy) => {
                     return Err("Current mode is not TUN mode".to_string());
                 }
                 Some(ProxyMode::SystemProxy) => {
                     return Err("Current mode is not System Proxy mode".to_string());
                 }
+                Some(ProxyMode::Pac) => {
+                    return Err("Current mode is not PAC mode".to_string());
+                }
                 None => {
                     return Err("No running process found".to_string());
                 }
@@ -460,7 +558,19 @@ pub async fn reload_config(is_tun: bool) -> Result<String, String> {
         };
 
         // 检查是否是特权模式（TUN模式）
-        let is_privileged = matches!(current_mode, Some(ProxyMode::TunProxy));
+        let is_privileged = current_mode.as_ref().is_some_and(|m| m.is_tun());
+
+        // 前端重新生成的配置需要再次补充网桥排除与分流规则
+        #[cfg(target_os = "linux")]
+        if is_privileged {
+            let config_path = match PROCESS_MANAGER.lock() {
+                Ok(m) => m.config_path.clone(),
+                Err(e) => e.into_inner().config_path.clone(),
+            };
+            if let Some(config_path) = config_path {
+                crate::vpn::linux::prepare_tun_config(&app, &config_path);
+            }
+        }
 
         // 直接查找 sing-box 进程并发送 HUP 信号
         let output = if is_privileged && !password.is_empty() {
@@ -482,6 +592,12 @@ pub async fn reload_config(is_tun: bool) -> Result<String, String> {
         };
 
         if output.status.success() {
+            // PAC 模式下同步更新 PAC 内容
+            if current_mode == Some(ProxyMode::Pac) {
+                refresh_pac(&app);
+            }
+            // SIGHUP 会重置选择器，重新应用保存的选择
+            tauri::async_runtime::spawn(selection::restore(app.clone()));
             Ok("Configuration reloaded successfully".to_string())
         } else {
             let error = String::from_utf8_lossy(&output.stderr);
@@ -493,22 +609,27 @@ pub async fn reload_config(is_tun: bool) -> Result<String, String> {
     {
         // Windows 平台不支持 SIGHUP 信号，需要通过重启进程来重载配置
         let _ = is_tun;
-        let config_path = {
+        let (config_path, current_mode) = {
             let manager = match PROCESS_MANAGER.lock() {
                 Ok(m) => m,
                 Err(e) => e.into_inner(),
             };
-            manager.config_path.clone()
+            (manager.config_path.clone(), manager.current_mode.clone())
         };
+        if current_mode == Some(ProxyMode::Pac) {
+            refresh_pac(&app);
+        }
 
         let sidecar_path = helper::get_sidecar_path(Path::new("sing-box"))
             .map_err(|e| format!("Failed to get sidecar path: {}", e))?;
         PlatformVpnProxy::restart(sidecar_path, config_path.unwrap_or_default());
+        tauri::async_runtime::spawn(selection::restore(app.clone()));
         Ok("Configuration reload attempted by restarting process".to_string())
     }
 
     #[cfg(not(any(unix, target_os = "windows")))]
     {
+        let _ = app;
         Err("SIGHUP signal is not supported on this platform".to_string())
     }
 }
@@ -1,4 +1,12 @@
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
 use tauri_plugin_sql::{Migration, MigrationKind};
+use tauri_plugin_store::StoreExt;
+use tokio::sync::OnceCell;
+
+// 与前端 SSI_STORE_KEY 一致
+const SUBSCRIPTION_STORE_KEY: &str = "selected_subscription_identifier";
 
 // 定义一个 sql_1 的变量 来存储 SQL 语句
 
@@ -34,3 +42,63 @@ pub fn get_migrations() -> Vec<Migration> {
         kind: MigrationKind::Up,
     }]
 }
+
+// Rust 侧使用的表，不放在前端迁移中：前端可能尚未加载数据库，且迁移版本由前端插件管理
+const RUST_SCHEMA: &str = r#"
+-- 节点延迟测试记录
+CREATE TABLE IF NOT EXISTS latency_results (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    identifier TEXT NOT NULL DEFAULT '',  -- 订阅标识，未知时为空
+    tag TEXT NOT NULL,                    -- 节点名称
+    delay_ms INTEGER,                     -- 延迟(毫秒)，失败时为 NULL
+    error TEXT,                           -- 失败原因
+    test_url TEXT NOT NULL,               -- 测试地址
+    tested_at INTEGER NOT NULL            -- 测试时间(Unix 秒)
+);
+CREATE INDEX IF NOT EXISTS idx_latency_results_tag
+    ON latency_results (identifier, tag, tested_at);
+-- 每个订阅下选择器选中的节点
+CREATE TABLE IF NOT EXISTS selector_choices (
+    identifier TEXT NOT NULL,             -- 订阅标识，未知时为空
+    selector TEXT NOT NULL,               -- 选择器名称
+    node TEXT NOT NULL,                   -- 选中的节点
+    updated_at INTEGER NOT NULL,          -- 更新时间(Unix 秒)
+    PRIMARY KEY (identifier, selector)
+);
+"#;
+
+static POOL: OnceCell<SqlitePool> = OnceCell::const_new();
+
+/// 创建 Rust 侧使用的表
+pub async fn init_schema(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    sqlx::raw_sql(RUST_SCHEMA).execute(pool).await?;
+    Ok(())
+}
+
+/// 与前端共用 app_config_dir 下的 data.db，首次调用时建立连接
+pub async fn pool(app: &AppHandle) -> anyhow::Result<&'static SqlitePool> {
+    POOL.get_or_try_init(|| async {
+        let dir = app.path().app_config_dir()?;
+        std::fs::create_dir_all(&dir)?;
+        let options = SqliteConnectOptions::new()
+            .filename(dir.join("data.db"))
+            .create_if_missing(true)
+            .busy_timeout(Duration::from_secs(5));
+        let pool = SqlitePoolOptions::new()
+            .max_connections(2)
+            .connect_with(options)
+            .await?;
+        init_schema(&pool).await?;
+        Ok(pool)
+    })
+    .await
+}
+
+/// 当前选中的订阅标识
+pub fn selected_subscription(app: &AppHandle) -> Option<String> {
+    app.store("settings.json")
+        .ok()
+        .and_then(|store| store.get(SUBSCRIPTION_STORE_KEY))
+        .and_then(|value| value.as_str().map(|s| s.to_string()))
+        .filter(|s| !s.is_empty())
+}
@@ -0,0 +1,271 @@
+use serde::Serialize;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri_plugin_http::reqwest::{self, header::DATE, redirect::Policy};
+use tokio::net::UdpSocket;
+use tokio::task::JoinSet;
+
+use super::DiagnosticTargets;
+
+// NTP 时间从 1900 年开始计算
+const NTP_UNIX_OFFSET: i64 = 2_208_988_800;
+
+/// 单个时间来源的测量结果
+#[derive(Clone, Debug, Serialize)]
+pub struct ClockSample {
+    pub source: String,
+    /// 服务器时间减本地时间，正数表示本地时间偏慢
+    pub offset_ms: Option<i64>,
+    pub error: Option<String>,
+}
+
+/// 时钟偏差检测结果
+#[derive(Clone, Debug, Serialize)]
+pub struct ClockSkewReport {
+    /// 采用的偏差（NTP 优先，否则取 HTTP 结果的中位数）
+    pub offset_ms: Option<i64>,
+    pub tolerance_secs: u64,
+    /// 偏差超过容忍范围，VMess 等协议会握手失败
+    pub exceeded: bool,
+    pub samples: Vec<ClockSample>,
+    /// 检测时间（Unix 秒）
+    pub checked_at: u64,
+}
+
+fn unix_millis(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(d) => d.as_millis() as i64,
+        Err(e) => -(e.duration().as_millis() as i64),
+    }
+}
+
+/// 公历日期到 Unix 天数
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// 解析 IMF-fixdate 格式的 Date 头，如 Sun, 06 Nov 1994 08:49:37 GMT，返回 Unix 秒
+fn parse_http_date(value: &str) -> Option<i64> {
+    let parts: Vec<&str> = value.split_whitespace().collect();
+    if parts.len() != 6 || parts[5] != "GMT" {
+        return None;
+    }
+    let day: i64 = parts[1].parse().ok()?;
+    let month = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ]
+    .iter()
+    .position(|m| *m == parts[2])? as i64
+        + 1;
+    let year: i64 = parts[3].parse().ok()?;
+    let time: Vec<i64> = parts[4]
+        .split(':')
+        .map(|s| s.parse().ok())
+        .collect::<Option<_>>()?;
+    if time.len() != 3 || !(1..=31).contains(&day) || time[0] > 23 || time[1] > 59 || time[2] > 60 {
+        return None;
+    }
+    Some(days_from_civil(year, month, day) * 86_400 + time[0] * 3600 + time[1] * 60 + time[2])
+}
+
+/// 直连访问地址，用 Date 头与请求中点的本地时间比较
+async fn http_offset(client: reqwest::Client, url: String) -> ClockSample {
+    let result = async {
+        let sent = SystemTime::now();
+        let started = Instant::now();
+        let response = client
+            .head(&url)
+            .send()
+            .await
+            .map_err(|e| format!("HEAD {}: {}", url, e))?;
+        let midpoint = unix_millis(sent) + started.elapsed().as_millis() as i64 / 2;
+        let date = response
+            .headers()
+            .get(DATE)
+            .and_then(|value| value.to_str().ok())
+            .ok_or_else(|| format!("{} returned no Date header", url))?;
+        let server =
+            parse_http_date(date).ok_or_else(|| format!("invalid Date header: {}", date))?;
+        // Date 头只精确到秒，取该秒的中间值
+        Ok(server * 1000 + 500 - midpoint)
+    }
+    .await;
+    ClockSample {
+        source: url,
+        offset_ms: result.as_ref().ok().copied(),
+        error: result.err(),
+    }
+}
+
+fn ntp_timestamp_millis(bytes: &[u8]) -> i64 {
+    let seconds = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as i64;
+    let fraction = u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as i64;
+    (seconds - NTP_UNIX_OFFSET) * 1000 + ((fraction * 1000) >> 32)
+}
+
+/// SNTP 查询，按 RFC 4330 计算偏差
+async fn ntp_offset(server: &str) -> Result<i64, String> {
+    let address = if server.contains(':') && !server.ends_with(']') {
+        server.to_string()
+    } else {
+        format!("{}:123", server)
+    };
+    let socket = UdpSocket::bind("0.0.0.0:0")
+        .await
+        .map_err(|e| e.to_string())?;
+    socket
+        .connect(&address)
+        .await
+        .map_err(|e| format!("connect {}: {}", address, e))?;
+
+    // LI = 0，VN = 4，Mode = 3（客户端）
+    let mut request = [0u8; 48];
+    request[0] = 0x23;
+    let t1 = unix_millis(SystemTime::now());
+    socket
+        .send(&request)
+        .await
+        .map_err(|e| format!("send to {}: {}", address, e))?;
+    let mut response = [0u8; 48];
+    let len = socket
+        .recv(&mut response)
+        .await
+        .map_err(|e| format!("receive from {}: {}", address, e))?;
+    let t4 = unix_millis(SystemTime::now());
+    if len < 48 || response[0] & 0x07 != 4 {
+        return Err(format!("invalid NTP response from {}", address));
+    }
+    if response[1] == 0 {
+        return Err(format!("{} sent kiss-of-death", address));
+    }
+    let t2 = ntp_timestamp_millis(&response[32..40]);
+    let t3 = ntp_timestamp_millis(&response[40..48]);
+    Ok(((t2 - t1) + (t3 - t4)) / 2)
+}
+
+fn median(mut values: Vec<i64>) -> Option<i64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_unstable();
+    let mid = values.len() / 2;
+    Some(if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2
+    } else {
+        values[mid]
+    })
+}
+
+/// 比较本地时间与 NTP 服务器或多个 HTTP 地址的时间
+pub async fn check(targets: &DiagnosticTargets) -> ClockSkewReport {
+    let timeout = Duration::from_millis(targets.timeout_ms);
+    let mut samples = Vec::new();
+
+    let mut ntp = None;
+    if let Some(server) = &targets.ntp_server {
+        let result = tokio::time::timeout(timeout, ntp_offset(server))
+            .await
+            .unwrap_or_else(|_| Err(format!("{} timed out", server)));
+        ntp = result.as_ref().ok().copied();
+        samples.push(ClockSample {
+            source: format!("ntp://{}", server),
+            offset_ms: ntp,
+            error: result.err(),
+        });
+    }
+
+    let mut http = Vec::new();
+    if ntp.is_none() {
+        let client = reqwest::ClientBuilder::new()
+            .timeout(timeout)
+            .redirect(Policy::none())
+            .no_proxy()
+            .build()
+            .unwrap();
+        let mut set = JoinSet::new();
+        for url in targets.time_endpoints.iter().cloned() {
+            set.spawn(http_offset(client.clone(), url));
+        }
+        while let Some(joined) = set.join_next().await {
+            if let Ok(sample) = joined {
+                http.extend(sample.offset_ms);
+                samples.push(sample);
+            }
+        }
+    }
+
+    let offset_ms = ntp.or_else(|| median(http));
+    let tolerance_secs = targets.clock_tolerance_secs;
+    ClockSkewReport {
+        offset_ms,
+        tolerance_secs,
+        exceeded: offset_ms.is_some_and(|offset| offset.unsigned_abs() > tolerance_secs * 1000),
+        samples,
+        checked_at: unix_millis(SystemTime::now()) as u64 / 1000,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_imf_fixdate() {
+        assert_eq!(
+            parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"),
+            Some(784111777)
+        );
+        assert_eq!(
+            parse_http_date("Thu, 29 Feb 2024 23:59:59 GMT"),
+            Some(1709251199)
+        );
+        assert_eq!(parse_http_date("Sunday, 06-Nov-94 08:49:37 GMT"), None);
+        assert_eq!(parse_http_date("Sun, 06 Nov 1994 08:49:37 CET"), None);
+    }
+
+    #[test]
+    fn median_of_offsets() {
+        assert_eq!(median(vec![]), None);
+        assert_eq!(median(vec![300, -100, 200]), Some(200));
+        assert_eq!(median(vec![400, 100, 200, 300]), Some(250));
+    }
+
+    #[tokio::test]
+    async fn detects_skew_from_stand_in_ntp_server() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let address = server.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 48];
+            let (_, peer) = server.recv_from(&mut buf).await.unwrap();
+            // 服务器时间比本地快 120 秒
+            let now = unix_millis(SystemTime::now()) + 120_000;
+            let seconds = (now / 1000 + NTP_UNIX_OFFSET) as u32;
+            let fraction = (((now % 1000) << 32) / 1000) as u32;
+            let mut response = [0u8; 48];
+            response[0] = 0x24;
+            response[1] = 2;
+            for offset in [32, 40] {
+                response[offset..offset + 4].copy_from_slice(&seconds.to_be_bytes());
+                response[offset + 4..offset + 8].copy_from_slice(&fraction.to_be_bytes());
+            }
+            server.send_to(&response, peer).await.unwrap();
+        });
+
+        let targets = DiagnosticTargets {
+            ntp_server: Some(address),
+            time_endpoints: vec![],
+            timeout_ms: 1000,
+            ..Default::default()
+        };
+        let report = check(&targets).await;
+        let offset = report.offset_ms.unwrap();
+        assert!((119_000..=121_000).contains(&offset), "offset {}", offset);
+        assert!(report.exceeded);
+        assert_eq!(report.samples.len(), 1);
+    }
+}
@@ -0,0 +1,494 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+use tauri_plugin_http::reqwest;
+use tokio::net::UdpSocket;
+use tokio::task::JoinSet;
+
+pub(super) const TYPE_A: u16 = 1;
+const TYPE_TXT: u16 = 16;
+pub(super) const TYPE_AAAA: u16 = 28;
+
+/// 检测方式
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DnsLeakMode {
+    /// 通过系统解析随机子域名，再向检测服务查询访问过它的解析器
+    #[default]
+    Service,
+    /// 向 DNS 服务器查询 TXT 记录，由权威服务器返回实际请求它的解析器地址
+    Whoami,
+}
+
+/// DNS 泄露检测参数
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DnsLeakOptions {
+    pub mode: DnsLeakMode,
+    /// 获取检测会话 id 的地址
+    pub session_url: String,
+    /// 要解析的域名，{id} 为会话 id，{n} 为序号
+    pub query_host: String,
+    /// 查询检测结果的地址
+    pub result_url: String,
+    /// whoami 方式查询的域名，{rand} 会替换为随机字符串以避开缓存
+    pub whoami_name: String,
+    /// whoami 方式使用的 DNS 服务器，为空时使用系统配置的第一个服务器
+    pub server: Option<String>,
+    /// 查询次数
+    pub count: u32,
+    pub timeout_ms: u64,
+}
+
+impl Default for DnsLeakOptions {
+    fn default() -> Self {
+        Self {
+            mode: DnsLeakMode::Service,
+            session_url: "https://bash.ws/id".to_string(),
+            query_host: "{n}.{id}.bash.ws".to_string(),
+            result_url: "https://bash.ws/dnsleak/test/{id}?json".to_string(),
+            whoami_name: "o-o.myaddr.l.google.com".to_string(),
+            server: None,
+            count: 10,
+            timeout_ms: 5000,
+        }
+    }
+}
+
+/// 检测到的解析器
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ObservedResolver {
+    pub ip: String,
+    pub country: Option<String>,
+    pub asn: Option<String>,
+}
+
+/// 检测结果
+#[derive(Clone, Debug, Serialize)]
+pub struct DnsLeakReport {
+    pub mode: DnsLeakMode,
+    pub queries: u32,
+    pub resolvers: Vec<ObservedResolver>,
+    pub duration_ms: u64,
+}
+
+fn random_label() -> String {
+    let mut rng = rand::rng();
+    (0..12)
+        .map(|_| (b'a' + rng.random_range(0..26u8)) as char)
+        .collect()
+}
+
+fn push_resolver(resolvers: &mut Vec<ObservedResolver>, resolver: ObservedResolver) {
+    if !resolvers.iter().any(|r| r.ip == resolver.ip) {
+        resolvers.push(resolver);
+    }
+}
+
+/// 构造指定类型的查询报文
+pub(super) fn build_query(id: u16, name: &str, record_type: u16) -> Result<Vec<u8>, String> {
+    let mut packet = Vec::with_capacity(name.len() + 18);
+    packet.extend_from_slice(&id.to_be_bytes());
+    // 标准查询，期望递归
+    packet.extend_from_slice(&[0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    for label in name.trim_end_matches('.').split('.') {
+        if label.is_empty() || label.len() > 63 {
+            return Err(format!("invalid domain name {}", name));
+        }
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label.as_bytes());
+    }
+    packet.push(0);
+    packet.extend_from_slice(&record_type.to_be_bytes());
+    packet.extend_from_slice(&1u16.to_be_bytes());
+    Ok(packet)
+}
+
+/// 跳过报文中的域名（含压缩指针），返回其后的位置
+fn skip_name(packet: &[u8], mut pos: usize) -> Option<usize> {
+    loop {
+        let len = *packet.get(pos)? as usize;
+        if len == 0 {
+            return Some(pos + 1);
+        }
+        if len & 0xc0 == 0xc0 {
+            return Some(pos + 2);
+        }
+        pos += len + 1;
+    }
+}
+
+fn read_u16(packet: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([
+        *packet.get(pos)?,
+        *packet.get(pos + 1)?,
+    ]))
+}
+
+/// 解析应答中的所有记录，返回记录类型与数据
+pub(super) fn parse_answers(packet: &[u8], id: u16) -> Result<Vec<(u16, &[u8])>, String> {
+    let malformed = || "malformed DNS response".to_string();
+    if read_u16(packet, 0).ok_or_else(malformed)? != id {
+        return Err("DNS response id mismatch".to_string());
+    }
+    let rcode = packet.get(3).ok_or_else(malformed)? & 0x0f;
+    if rcode != 0 {
+        return Err(format!("DNS server returned rcode {}", rcode));
+    }
+    let questions = read_u16(packet, 4).ok_or_else(malformed)?;
+    let answers = read_u16(packet, 6).ok_or_else(malformed)?;
+
+    let mut pos = 12;
+    for _ in 0..questions {
+        pos = skip_name(packet, pos).ok_or_else(malformed)? + 4;
+    }
+    let mut records = Vec::new();
+    for _ in 0..answers {
+        pos = skip_name(packet, pos).ok_or_else(malformed)?;
+        let record_type = read_u16(packet, pos).ok_or_else(malformed)?;
+        let rdlength = read_u16(packet, pos + 8).ok_or_else(malformed)? as usize;
+        let rdata = packet
+            .get(pos + 10..pos + 10 + rdlength)
+            .ok_or_else(malformed)?;
+        pos += 10 + rdlength;
+        records.push((record_type, rdata));
+    }
+    Ok(records)
+}
+
+/// 解析应答中所有 TXT 记录的字符串
+fn parse_txt_answers(packet: &[u8], id: u16) -> Result<Vec<String>, String> {
+    let mut texts = Vec::new();
+    for (_, rdata) in parse_answers(packet, id)?
+        .into_iter()
+        .filter(|(record_type, _)| *record_type == TYPE_TXT)
+    {
+        let mut i = 0;
+        while i < rdata.len() {
+            let len = rdata[i] as usize;
+            let text = rdata
+                .get(i + 1..i + 1 + len)
+                .ok_or_else(|| "malformed DNS response".to_string())?;
+            texts.push(String::from_utf8_lossy(text).to_string());
+            i += len + 1;
+        }
+    }
+    Ok(texts)
+}
+
+/// 系统配置的第一个 DNS 服务器
+#[cfg(unix)]
+fn system_nameserver() -> Result<String, String> {
+    let content = std::fs::read_to_string("/etc/resolv.conf").map_err(|e| e.to_string())?;
+    content
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("nameserver"))
+        .map(|server| server.trim().to_string())
+        .filter(|server| !server.is_empty())
+        .ok_or_else(|| "no nameserver in /etc/resolv.conf".to_string())
+}
+
+#[cfg(target_os = "windows")]
+fn system_nameserver() -> Result<String, String> {
+    Err("Please specify a DNS server".to_string())
+}
+
+fn server_address(server: &str) -> String {
+    if server.parse::<IpAddr>().is_ok_and(|ip| ip.is_ipv6()) {
+        format!("[{}]:53", server)
+    } else if server.contains(':') {
+        server.to_string()
+    } else {
+        format!("{}:53", server)
+    }
+}
+
+async fn whoami_query(server: &str, name: &str) -> Result<Vec<String>, String> {
+    let bind = if server.starts_with('[') {
+        "[::]:0"
+    } else {
+        "0.0.0.0:0"
+    };
+    let socket = UdpSocket::bind(bind).await.map_err(|e| e.to_string())?;
+    socket
+        .connect(server)
+        .await
+        .map_err(|e| format!("connect {}: {}", server, e))?;
+    let id: u16 = rand::rng().random();
+    socket
+        .send(&build_query(id, name, TYPE_TXT)?)
+        .await
+        .map_err(|e| format!("query {}: {}", name, e))?;
+    let mut buf = [0u8; 1500];
+    let len = socket
+        .recv(&mut buf)
+        .await
+        .map_err(|e| format!("query {}: {}", name, e))?;
+    parse_txt_answers(&buf[..len], id)
+}
+
+async fn run_whoami(options: &DnsLeakOptions) -> Result<Vec<ObservedResolver>, String> {
+    let server = match &options.server {
+        Some(server) => server.clone(),
+        None => system_nameserver()?,
+    };
+    let server = server_address(&server);
+    let timeout = Duration::from_millis(options.timeout_ms);
+
+    let mut set = JoinSet::new();
+    for _ in 0..options.count {
+        let name = options.whoami_name.replace("{rand}", &random_label());
+        let server = server.clone();
+        set.spawn(async move {
+            tokio::time::timeout(timeout, whoami_query(&server, &name))
+                .await
+                .unwrap_or_else(|_| Err(format!("query {} timed out", name)))
+        });
+    }
+
+    let mut resolvers = Vec::new();
+    let mut last_error = None;
+    while let Some(joined) = set.join_next().await {
+        match joined.map_err(|e| e.to_string()).and_then(|r| r) {
+            Ok(texts) => {
+                // 只取能解析为地址的字符串，忽略 "ns"、edns0-client-subnet 等说明
+                for text in texts {
+                    if let Ok(ip) = text.trim().parse::<IpAddr>() {
+                        push_resolver(
+                            &mut resolvers,
+                            ObservedResolver {
+                                ip: ip.to_string(),
+                                country: None,
+                                asn: None,
+                            },
+                        );
+                    }
+                }
+            }
+            Err(e) => last_error = Some(e),
+        }
+    }
+    match (resolvers.is_empty(), last_error) {
+        (true, Some(error)) => Err(error),
+        _ => Ok(resolvers),
+    }
+}
+
+/// 解析检测服务的结果，只保留 type 为 dns 的条目（没有 type 字段时全部保留）
+fn parse_service_results(body: &str) -> Result<Vec<ObservedResolver>, String> {
+    let entries: Vec<Value> =
+        serde_json::from_str(body).map_err(|e| format!("invalid result: {}", e))?;
+    let mut resolvers = Vec::new();
+    for entry in entries {
+        if entry["type"].as_str().is_some_and(|t| t != "dns") {
+            continue;
+        }
+        let Some(ip) = entry["ip"].as_str() else {
+            continue;
+        };
+        let text = |key: &str| {
+            entry[key]
+                .as_str()
+                .filter(|s| !s.is_empty())
+                .map(|s| s.to_string())
+        };
+        push_resolver(
+            &mut resolvers,
+            ObservedResolver {
+                ip: ip.to_string(),
+                country: text("country_name").or_else(|| text("country")),
+                asn: text("asn"),
+            },
+        );
+    }
+    Ok(resolvers)
+}
+
+async fn run_service(
+    options: &DnsLeakOptions,
+    client: &reqwest::Client,
+) -> Result<Vec<ObservedResolver>, String> {
+    let id = client
+        .get(&options.session_url)
+        .send()
+        .await
+        .map_err(|e| format!("GET {}: {}", options.session_url, e))?
+        .text()
+        .await
+        .map_err(|e| e.to_string())?
+        .trim()
+        .to_string();
+    if id.is_empty() {
+        return Err("empty test session id".to_string());
+    }
+
+    // 解析结果并不重要（通常为 NXDOMAIN），只需让请求到达检测服务的权威服务器
+    let timeout = Duration::from_millis(options.timeout_ms);
+    let mut set = JoinSet::new();
+    for n in 1..=options.count {
+        let host = options
+            .query_host
+            .replace("{id}", &id)
+            .replace("{n}", &n.to_string());
+        set.spawn(async move {
+            let _ = tokio::time::timeout(timeout, tokio::net::lookup_host((host, 0))).await;
+        });
+    }
+    while set.join_next().await.is_some() {}
+
+    let url = options.result_url.replace("{id}", &id);
+    let body = client
+        .get(&url)
+        .send()
+        .await
+        .map_err(|e| format!("GET {}: {}", url, e))?
+        .text()
+        .await
+        .map_err(|e| e.to_string())?;
+    parse_service_results(&body)
+}
+
+/// 执行 DNS 泄露检测。client 用于访问检测服务的 HTTP 接口
+pub async fn run(
+    options: &DnsLeakOptions,
+    client: &reqwest::Client,
+) -> Result<DnsLeakReport, String> {
+    let started = Instant::now();
+    let resolvers = match options.mode {
+        DnsLeakMode::Service => run_service(options, client).await?,
+        DnsLeakMode::Whoami => run_whoami(options).await?,
+    };
+    Ok(DnsLeakReport {
+        mode: options.mode,
+        queries: options.count,
+        resolvers,
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// 模拟权威服务器：对每个 TXT 查询返回请求方的地址，并记录查询的域名
+    async fn stand_in_server(answer: Option<&'static str>) -> (String, Arc<Mutex<Vec<String>>>) {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap().to_string();
+        let names = Arc::new(Mutex::new(Vec::new()));
+        let seen = names.clone();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 512];
+            while let Ok((len, peer)) = socket.recv_from(&mut buf).await {
+                let query = &buf[..len];
+                let end = skip_name(query, 12).unwrap();
+                let mut name = Vec::new();
+                let mut pos = 12;
+                while query[pos] != 0 {
+                    let l = query[pos] as usize;
+                    name.push(String::from_utf8_lossy(&query[pos + 1..pos + 1 + l]).to_string());
+                    pos += l + 1;
+                }
+                seen.lock().unwrap().push(name.join("."));
+
+                let text = answer
+                    .map(|a| a.to_string())
+                    .unwrap_or_else(|| peer.ip().to_string());
+                let mut response = query[..end + 4].to_vec();
+                // QR=1，RA=1，一条应答
+                response[2] = 0x81;
+                response[3] = 0x80;
+                response[6..8].copy_from_slice(&1u16.to_be_bytes());
+                response.extend_from_slice(&[0xc0, 0x0c]);
+                response.extend_from_slice(&TYPE_TXT.to_be_bytes());
+                response.extend_from_slice(&1u16.to_be_bytes());
+                response.extend_from_slice(&60u32.to_be_bytes());
+                response.extend_from_slice(&((text.len() + 1) as u16).to_be_bytes());
+                response.push(text.len() as u8);
+                response.extend_from_slice(text.as_bytes());
+                let _ = socket.send_to(&response, peer).await;
+            }
+        });
+        (addr, names)
+    }
+
+    fn whoami_options(server: String) -> DnsLeakOptions {
+        DnsLeakOptions {
+            mode: DnsLeakMode::Whoami,
+            whoami_name: "{rand}.leak.test".to_string(),
+            server: Some(server),
+            count: 5,
+            timeout_ms: 1000,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_resolver_seen_by_stand_in() {
+        let (server, names) = stand_in_server(None).await;
+        let options = whoami_options(server);
+        let report = run(&options, &reqwest::Client::new()).await.unwrap();
+
+        assert_eq!(report.mode, DnsLeakMode::Whoami);
+        assert_eq!(
+            report.resolvers,
+            vec![ObservedResolver {
+                ip: "127.0.0.1".to_string(),
+                country: None,
+                asn: None,
+            }]
+        );
+        let names = names.lock().unwrap();
+        assert_eq!(names.len(), 5);
+        assert!(names.iter().all(|n| n.ends_with(".leak.test")));
+        // 每次查询使用不同的随机子域名
+        let mut unique = names.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), 5);
+    }
+
+    #[tokio::test]
+    async fn ignores_non_address_txt() {
+        let (server, _) = stand_in_server(Some("edns0-client-subnet 1.2.3.0/24")).await;
+        let report = run(&whoami_options(server), &reqwest::Client::new())
+            .await
+            .unwrap();
+        assert!(report.resolvers.is_empty());
+    }
+
+    #[tokio::test]
+    async fn fails_when_server_does_not_answer() {
+        let silent = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let mut options = whoami_options(silent.local_addr().unwrap().to_string());
+        options.count = 1;
+        options.timeout_ms = 200;
+        let error = run(&options, &reqwest::Client::new()).await.unwrap_err();
+        assert!(error.contains("timed out"));
+    }
+
+    #[test]
+    fn parses_service_results() {
+        let body = r#"[
+            {"ip":"203.0.113.7","country_name":"Japan","asn":"AS64500","type":"ip"},
+            {"ip":"198.51.100.53","country_name":"Japan","asn":"AS64500","type":"dns"},
+            {"ip":"198.51.100.53","country_name":"Japan","asn":"AS64500","type":"dns"},
+            {"ip":"192.0.2.1","country_name":"","asn":"","type":"dns"},
+            {"ip":"0","type":"conclusion"}
+        ]"#;
+        let resolvers = parse_service_results(body).unwrap();
+        assert_eq!(resolvers.len(), 2);
+        assert_eq!(resolvers[0].ip, "198.51.100.53");
+        assert_eq!(resolvers[0].country.as_deref(), Some("Japan"));
+        assert_eq!(resolvers[1].country, None);
+    }
+
+    #[test]
+    fn server_address_adds_default_port() {
+        assert_eq!(server_address("192.0.2.53"), "192.0.2.53:53");
+        assert_eq!(server_address("127.0.0.1:5353"), "127.0.0.1:5353");
+        assert_eq!(server_address("2001:db8::53"), "[2001:db8::53]:53");
+    }
+}
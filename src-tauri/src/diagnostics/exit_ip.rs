@@ -0,0 +1,232 @@
+use maxminddb::{geoip2, Reader};
+use serde::Serialize;
+use serde_json::Value;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tauri_plugin_http::reqwest::{self, redirect::Policy};
+
+use super::DiagnosticTargets;
+use crate::core::{self, ProxyMode};
+
+/// IP 归属信息，来自本地 MMDB 数据库
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct GeoInfo {
+    /// ISO 国家代码
+    pub country: Option<String>,
+    pub country_name: Option<String>,
+    pub asn: Option<u32>,
+    pub as_org: Option<String>,
+}
+
+/// 一条出口的检测结果
+#[derive(Clone, Debug, Serialize)]
+pub struct Egress {
+    pub ip: Option<String>,
+    pub geo: Option<GeoInfo>,
+    pub latency_ms: u64,
+    pub error: Option<String>,
+}
+
+/// 直连与代理出口对比
+#[derive(Clone, Debug, Serialize)]
+pub struct ExitIpReport {
+    pub direct: Egress,
+    pub proxied: Egress,
+    /// 两个出口都获取成功时才有值
+    pub differ: Option<bool>,
+    /// 使用到的 MMDB 数据库
+    pub databases: Vec<String>,
+}
+
+/// 兼容纯文本与常见 JSON 格式（ip / query / origin 字段）
+fn parse_ip(body: &str) -> Option<IpAddr> {
+    let body = body.trim();
+    if let Ok(ip) = body.parse() {
+        return Some(ip);
+    }
+    let json: Value = serde_json::from_str(body).ok()?;
+    ["ip", "query", "origin"]
+        .iter()
+        .filter_map(|key| json[key].as_str())
+        // httpbin 的 origin 可能是逗号分隔的多个地址
+        .find_map(|value| value.split(',').next()?.trim().parse().ok())
+}
+
+async fn query(client: reqwest::Client, endpoint: &str) -> (Result<IpAddr, String>, u64) {
+    let started = Instant::now();
+    let result = async {
+        let response = client
+            .get(endpoint)
+            .send()
+            .await
+            .map_err(|e| format!("GET {}: {}", endpoint, e))?;
+        let status = response.status();
+        let body = response.text().await.map_err(|e| e.to_string())?;
+        if !status.is_success() {
+            return Err(format!("GET {} returned {}", endpoint, status));
+        }
+        parse_ip(&body).ok_or_else(|| format!("no IP address in response: {}", body.trim()))
+    }
+    .await;
+    (result, started.elapsed().as_millis() as u64)
+}
+
+/// 目录下的所有 .mmdb 文件
+pub fn find_databases(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "mmdb"))
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+/// 在所有数据库中查询，按数据库类型合并国家与 ASN 信息
+fn lookup(readers: &[Reader<Vec<u8>>], ip: IpAddr) -> Option<GeoInfo> {
+    let mut geo = GeoInfo::default();
+    for reader in readers {
+        if reader.metadata.database_type.contains("ASN") {
+            if let Ok(asn) = reader.lookup::<geoip2::Asn>(ip) {
+                geo.asn = geo.asn.or(asn.autonomous_system_number);
+                geo.as_org = geo
+                    .as_org
+                    .or(asn.autonomous_system_organization.map(|s| s.to_string()));
+            }
+        } else if let Ok(record) = reader.lookup::<geoip2::Country>(ip) {
+            if let Some(country) = record.country {
+                geo.country = geo.country.or(country.iso_code.map(|s| s.to_string()));
+                geo.country_name = geo.country_name.or(country
+                    .names
+                    .and_then(|names| names.get("en").map(|s| s.to_string())));
+            }
+        }
+    }
+    (geo != GeoInfo::default()).then_some(geo)
+}
+
+fn egress(result: Result<IpAddr, String>, latency_ms: u64, readers: &[Reader<Vec<u8>>]) -> Egress {
+    match result {
+        Ok(ip) => Egress {
+            ip: Some(ip.to_string()),
+            geo: lookup(readers, ip),
+            latency_ms,
+            error: None,
+        },
+        Err(error) => Egress {
+            ip: None,
+            geo: None,
+            latency_ms,
+            error: Some(error),
+        },
+    }
+}
+
+/// TUN 模式下不经代理的请求同样会被 tun 接管，此时无法得到真实的直连出口
+fn direct_unavailable(mode: Option<&ProxyMode>) -> Option<String> {
+    mode.filter(|mode| mode.is_tun()).map(|mode| {
+        format!(
+            "direct egress unavailable in {:?} mode: requests are captured by the tun interface",
+            mode
+        )
+    })
+}
+
+/// 同时直连与通过代理查询出口 IP
+pub async fn check(targets: &DiagnosticTargets, databases: &[PathBuf]) -> ExitIpReport {
+    let session = core::current_session();
+    let unavailable = direct_unavailable(session.as_ref().map(|(mode, _)| mode));
+    let timeout = Duration::from_millis(targets.timeout_ms);
+    let direct_client = reqwest::ClientBuilder::new()
+        .timeout(timeout)
+        .redirect(Policy::none())
+        .no_proxy()
+        .build()
+        .unwrap();
+    let proxied_client =
+        reqwest::Proxy::all(format!("http://{}", targets.inbound)).and_then(|proxy| {
+            reqwest::ClientBuilder::new()
+                .timeout(timeout)
+                .proxy(proxy)
+                .build()
+        });
+
+    let endpoint = targets.ip_endpoint.as_str();
+    let ((direct, direct_ms), (proxied, proxied_ms)) = tokio::join!(
+        async {
+            match unavailable {
+                Some(reason) => (Err(reason), 0),
+                None => query(direct_client, endpoint).await,
+            }
+        },
+        async {
+            match proxied_client {
+                Ok(client) => query(client, endpoint).await,
+                Err(e) => (Err(e.to_string()), 0),
+            }
+        }
+    );
+
+    let mut readers = Vec::new();
+    let mut used = Vec::new();
+    for path in databases {
+        match Reader::open_readfile(path) {
+            Ok(reader) => {
+                readers.push(reader);
+                used.push(path.to_string_lossy().to_string());
+            }
+            Err(e) => log::warn!("[diagnostics] Failed to open {}: {}", path.display(), e),
+        }
+    }
+
+    let differ = match (&direct, &proxied) {
+        (Ok(direct), Ok(proxied)) => Some(direct != proxied),
+        _ => None,
+    };
+    ExitIpReport {
+        direct: egress(direct, direct_ms, &readers),
+        proxied: egress(proxied, proxied_ms, &readers),
+        differ,
+        databases: used,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_plain_and_json_responses() {
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+        assert_eq!(parse_ip("203.0.113.7\n"), Some(ip));
+        assert_eq!(parse_ip(r#"{"ip":"203.0.113.7"}"#), Some(ip));
+        assert_eq!(
+            parse_ip(r#"{"status":"success","query":"203.0.113.7"}"#),
+            Some(ip)
+        );
+        assert_eq!(
+            parse_ip(r#"{"origin":"203.0.113.7, 198.51.100.1"}"#),
+            Some(ip)
+        );
+        assert_eq!(
+            parse_ip("2001:db8::1"),
+            Some("2001:db8::1".parse().unwrap())
+        );
+        assert_eq!(parse_ip("<html>blocked</html>"), None);
+    }
+
+    #[test]
+    fn skips_direct_query_in_tun_modes() {
+        assert!(direct_unavailable(None).is_none());
+        assert!(direct_unavailable(Some(&ProxyMode::SystemProxy)).is_none());
+        assert!(direct_unavailable(Some(&ProxyMode::Pac)).is_none());
+        assert!(direct_unavailable(Some(&ProxyMode::TunProxy))
+            .unwrap()
+            .contains("TunProxy"));
+        assert!(direct_unavailable(Some(&ProxyMode::TunWithSystemProxy)).is_some());
+    }
+}
@@ -0,0 +1,157 @@
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_http::reqwest;
+use tauri_plugin_store::StoreExt;
+
+use crate::app_status::AppData;
+use crate::clash_api::{ClashApi, Endpoint};
+use crate::core;
+
+pub mod clock;
+pub mod connectivity;
+pub mod dns_leak;
+pub mod exit_ip;
+
+pub use clock::ClockSkewReport;
+pub use connectivity::{DiagnosticTargets, DiagnosticsReport};
+pub use dns_leak::{DnsLeakOptions, DnsLeakReport};
+pub use exit_ip::ExitIpReport;
+
+// 用户自定义的诊断目标，未设置的字段使用默认值
+const DIAGNOSTICS_TARGETS_STORE_KEY: &str = "diagnostics_targets_key";
+const DNS_LEAK_OPTIONS_STORE_KEY: &str = "dns_leak_options_key";
+// 存放 MMDB 数据库的目录，位于配置目录下
+const GEOIP_DIR: &str = "geoip";
+
+/// 读取诊断目标
+pub fn targets(app: &AppHandle) -> DiagnosticTargets {
+    app.store("settings.json")
+        .ok()
+        .and_then(|store| store.get(DIAGNOSTICS_TARGETS_STORE_KEY))
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
+}
+
+/// 保存到状态中，偏差过大时发送 clock-skew-warning 事件
+fn record_clock_skew(app: &AppHandle, report: ClockSkewReport) {
+    if report.exceeded {
+        log::warn!(
+            "[diagnostics] System clock is off by {:?} ms, exceeding {} s",
+            report.offset_ms,
+            report.tolerance_secs
+        );
+        if let Err(e) = app.emit("clock-skew-warning", &report) {
+            log::error!("Failed to emit clock-skew-warning event: {}", e);
+        }
+    }
+    app.state::<AppData>().set_clock_skew(report);
+}
+
+/// 检测本地时钟偏差
+pub async fn check_clock(app: &AppHandle) -> ClockSkewReport {
+    let report = clock::check(&targets(app)).await;
+    record_clock_skew(app, report.clone());
+    report
+}
+
+/// 分阶段检测代理连通性，每完成一个阶段发送 diagnostics-stage 事件
+#[tauri::command]
+pub async fn run_connectivity_diagnostics(
+    app: AppHandle,
+    targets: Option<DiagnosticTargets>,
+) -> DiagnosticsReport {
+    let targets = targets.unwrap_or_else(|| self::targets(&app));
+    let mut endpoint = Endpoint::current(&app);
+    if let Some(address) = &targets.clash_api {
+        endpoint.address = address.clone();
+    }
+    let config_path = core::current_session().map(|(_, path)| path);
+
+    let report = connectivity::run(
+        &targets,
+        &ClashApi::new(endpoint),
+        config_path.as_deref(),
+        |stage| {
+            if let Err(e) = app.emit("diagnostics-stage", stage) {
+                log::error!("Failed to emit diagnostics-stage event: {}", e);
+            }
+        },
+    )
+    .await;
+    if let Some(skew) = &report.clock_skew {
+        record_clock_skew(&app, skew.clone());
+    }
+    for stage in report.stages.iter() {
+        log::info!(
+            "[diagnostics] {:?}: {:?} in {} ms {}",
+            stage.stage,
+            stage.status,
+            stage.duration_ms,
+            stage
+                .error
+                .as_deref()
+                .or(stage.detail.as_deref())
+                .unwrap_or_default()
+        );
+    }
+    report
+}
+
+/// 对比直连与代理的出口 IP，并从配置目录 geoip 下的 MMDB 数据库查询归属
+#[tauri::command]
+pub async fn check_exit_ip(
+    app: AppHandle,
+    targets: Option<DiagnosticTargets>,
+) -> Result<ExitIpReport, String> {
+    let targets = targets.unwrap_or_else(|| self::targets(&app));
+    let dir = app
+        .path()
+        .app_config_dir()
+        .map_err(|e| e.to_string())?
+        .join(GEOIP_DIR);
+    let report = exit_ip::check(&targets, &exit_ip::find_databases(&dir)).await;
+    log::info!(
+        "[diagnostics] Exit IP direct={:?} proxied={:?} differ={:?}",
+        report.direct.ip,
+        report.proxied.ip,
+        report.differ
+    );
+    Ok(report)
+}
+
+/// DNS 泄露检测，返回实际访问检测服务的解析器
+#[tauri::command]
+pub async fn run_dns_leak_test(
+    app: AppHandle,
+    options: Option<DnsLeakOptions>,
+) -> Result<DnsLeakReport, String> {
+    let options = options.unwrap_or_else(|| {
+        app.store("settings.json")
+            .ok()
+            .and_then(|store| store.get(DNS_LEAK_OPTIONS_STORE_KEY))
+            .and_then(|value| serde_json::from_value(value).ok())
+            .unwrap_or_default()
+    });
+    // 代理运行时通过代理访问检测服务的接口，避免接口本身被阻断
+    let mut builder =
+        reqwest::ClientBuilder::new().timeout(std::time::Duration::from_millis(options.timeout_ms));
+    if core::current_session().is_some() {
+        let proxy = reqwest::Proxy::all(format!("http://{}", targets(&app).inbound))
+            .map_err(|e| e.to_string())?;
+        builder = builder.proxy(proxy);
+    }
+    let client = builder.build().map_err(|e| e.to_string())?;
+
+    let report = dns_leak::run(&options, &client).await?;
+    log::info!(
+        "[diagnostics] DNS leak test ({:?}) observed resolvers: {:?}",
+        report.mode,
+        report.resolvers.iter().map(|r| &r.ip).collect::<Vec<_>>()
+    );
+    Ok(report)
+}
+
+/// 检测本地时钟与网络时间的偏差
+#[tauri::command]
+pub async fn check_clock_skew(app: AppHandle) -> ClockSkewReport {
+    check_clock(&app).await
+}
@@ -0,0 +1,413 @@
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_store::StoreExt;
+
+use crate::clash_api::{self, ClashApi};
+use crate::core;
+use crate::database;
+use crate::latency::{self, LatencyResult};
+
+const FAILOVER_OPTIONS_STORE_KEY: &str = "failover_options_key";
+const MIN_INTERVAL_SECS: u64 = 5;
+// 延迟测试结果超过这么多个探测间隔即视为过期，不再作为切换依据
+const RESULT_MAX_AGE_INTERVALS: u64 = 10;
+// 切换前最多重新探测的候选节点数
+const MAX_CANDIDATE_PROBES: usize = 3;
+
+/// 自动切换节点的参数
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FailoverOptions {
+    pub enabled: bool,
+    /// 探测间隔（秒）
+    pub interval_secs: u64,
+    /// 连续失败多少次后切换
+    pub failure_threshold: u32,
+    /// 两次切换的最短间隔（秒）
+    pub cooldown_secs: u64,
+    /// 优先切换到的节点，靠前的优先；都不可用时选延迟最低的节点
+    pub priority: Vec<String>,
+    pub url: String,
+    pub timeout_ms: u64,
+}
+
+impl Default for FailoverOptions {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: 30,
+            failure_threshold: 3,
+            cooldown_secs: 300,
+            priority: vec![],
+            url: clash_api::DEFAULT_DELAY_URL.to_string(),
+            timeout_ms: clash_api::DEFAULT_DELAY_TIMEOUT_MS,
+        }
+    }
+}
+
+/// failover-switched 事件内容
+#[derive(Clone, Debug, Serialize)]
+pub struct FailoverEvent {
+    pub group: String,
+    pub from: String,
+    pub to: String,
+    /// 切换前重新探测新节点得到的延迟
+    pub delay_ms: u16,
+    pub failures: u32,
+    /// 切换时间（Unix 秒）
+    pub timestamp: u64,
+}
+
+/// 监控状态
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct FailoverStatus {
+    /// 正在探测的节点
+    pub current: Option<String>,
+    /// 连续失败次数
+    pub failures: u32,
+    pub last_delay_ms: Option<u16>,
+    pub last_switch: Option<FailoverEvent>,
+}
+
+lazy_static! {
+    static ref STATUS: Mutex<FailoverStatus> = Mutex::new(FailoverStatus::default());
+}
+
+/// 按切换顺序排列候选节点：先按优先列表，再按延迟从低到高，忽略 since 之前的结果
+fn rank_candidates(
+    current: &str,
+    members: &[String],
+    results: &[LatencyResult],
+    priority: &[String],
+    since: i64,
+) -> Vec<(String, u16)> {
+    let mut healthy: Vec<(&str, u16)> = results
+        .iter()
+        .filter(|r| r.tag != current && members.contains(&r.tag) && r.tested_at >= since)
+        .filter_map(|r| Some((r.tag.as_str(), r.delay_ms?)))
+        .collect();
+    healthy.sort_by_key(|(tag, delay)| {
+        let rank = priority
+            .iter()
+            .position(|p| p == tag)
+            .unwrap_or(priority.len());
+        (rank, *delay)
+    });
+    healthy
+        .into_iter()
+        .map(|(tag, delay)| (tag.to_string(), delay))
+        .collect()
+}
+
+/// 测试当前节点以外的选择器成员，供没有可用测试结果时排序候选节点
+async fn refresh_results(
+    api: &ClashApi,
+    current: &str,
+    members: &[String],
+    options: &FailoverOptions,
+    concurrency: usize,
+) -> Vec<LatencyResult> {
+    let tags = members.iter().filter(|m| *m != current).cloned().collect();
+    let test_options = latency::LatencyTestOptions {
+        url: options.url.clone(),
+        timeout_ms: options.timeout_ms,
+        concurrency,
+    };
+    latency::test_all(api, tags, &test_options, |_| {}).await
+}
+
+/// 读取自动切换参数
+pub fn options(app: &AppHandle) -> FailoverOptions {
+    app.store("settings.json")
+        .ok()
+        .and_then(|store| store.get(FAILOVER_OPTIONS_STORE_KEY))
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
+}
+
+fn reset() {
+    let mut status = STATUS.lock().unwrap_or_else(|e| e.into_inner());
+    status.current = None;
+    status.failures = 0;
+    status.last_delay_ms = None;
+}
+
+/// 探测当前节点，连续失败达到阈值时切换选择器
+async fn probe(
+    app: &AppHandle,
+    options: &FailoverOptions,
+    config_path: &str,
+    last_switch: &mut Option<Instant>,
+) -> Result<(), String> {
+    let api = ClashApi::current(app);
+    let group = core::exit_selector(config_path);
+    let selector = api
+        .proxy(&group)
+        .await
+        .map_err(|e| format!("query selector {}: {}", group, e))?;
+    let current = match selector.now.clone() {
+        Some(now) if selector.is_selector() => now,
+        _ => return Ok(()),
+    };
+
+    let failures = {
+        let result = api.delay(&current, &options.url, options.timeout_ms).await;
+        let mut status = STATUS.lock().unwrap_or_else(|e| e.into_inner());
+        // 用户手动切换后重新计数
+        if status.current.as_deref() != Some(current.as_str()) {
+            status.current = Some(current.clone());
+            status.failures = 0;
+        }
+        match result {
+            Ok(delay) => {
+                status.failures = 0;
+                status.last_delay_ms = Some(delay);
+                return Ok(());
+            }
+            Err(e) => {
+                status.failures += 1;
+                status.last_delay_ms = None;
+                log::warn!(
+                    "[failover] Probe of {} failed ({}/{}): {}",
+                    current,
+                    status.failures,
+                    options.failure_threshold,
+                    e
+                );
+                status.failures
+            }
+        }
+    };
+    if failures < options.failure_threshold {
+        return Ok(());
+    }
+    if last_switch.is_some_and(|at| at.elapsed() < Duration::from_secs(options.cooldown_secs)) {
+        log::info!("[failover] In cooldown, keeping {}", current);
+        return Ok(());
+    }
+
+    let identifier = database::selected_subscription(app).unwrap_or_default();
+    let pool = database::pool(app).await.map_err(|e| e.to_string())?;
+    let results = latency::latest(pool, &identifier)
+        .await
+        .map_err(|e| e.to_string())?;
+    let max_age = options.interval_secs.max(MIN_INTERVAL_SECS) * RESULT_MAX_AGE_INTERVALS;
+    let since = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs().saturating_sub(max_age) as i64)
+        .unwrap_or_default();
+    let members = selector.all.unwrap_or_default();
+    let mut candidates = rank_candidates(&current, &members, &results, &options.priority, since);
+    if candidates.is_empty() {
+        // 批量测试只在手动触发时运行，结果缺失或过期时直接探测选择器成员
+        log::info!(
+            "[failover] No recent latency results, probing members of {}",
+            group
+        );
+        let concurrency = latency::options(app).concurrency;
+        let fresh = refresh_results(&api, &current, &members, options, concurrency).await;
+        if let Err(e) = latency::save(pool, &identifier, &fresh).await {
+            log::warn!("[failover] Failed to save latency results: {}", e);
+        }
+        candidates = rank_candidates(&current, &members, &fresh, &options.priority, since);
+    }
+
+    // 测试结果只用于排序，切换前重新探测，确认节点此刻可用
+    let mut chosen = None;
+    for (tag, _) in candidates.into_iter().take(MAX_CANDIDATE_PROBES) {
+        match api.delay(&tag, &options.url, options.timeout_ms).await {
+            Ok(delay) => {
+                chosen = Some((tag, delay));
+                break;
+            }
+            Err(e) => log::info!("[failover] Candidate {} failed re-probe: {}", tag, e),
+        }
+    }
+    let Some((to, delay_ms)) = chosen else {
+        log::warn!("[failover] No healthy node to switch to from {}", current);
+        return Ok(());
+    };
+
+    api.select(&group, &to)
+        .await
+        .map_err(|e| format!("switch {} to {}: {}", group, to, e))?;
+    *last_switch = Some(Instant::now());
+    log::warn!(
+        "[failover] Switched {} from {} to {} ({} ms) after {} failures",
+        group,
+        current,
+        to,
+        delay_ms,
+        failures
+    );
+
+    let event = FailoverEvent {
+        group,
+        from: current,
+        to: to.clone(),
+        delay_ms,
+        failures,
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default(),
+    };
+    {
+        let mut status = STATUS.lock().unwrap_or_else(|e| e.into_inner());
+        status.current = Some(to);
+        status.failures = 0;
+        status.last_delay_ms = Some(delay_ms);
+        status.last_switch = Some(event.clone());
+    }
+    if let Err(e) = app.emit("failover-switched", event) {
+        log::error!("Failed to emit failover-switched event: {}", e);
+    }
+    Ok(())
+}
+
+/// 启动节点健康监控，未开启或内核未运行时只等待
+pub fn start(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut last_switch = None;
+        loop {
+            let options = options(&app);
+            tokio::time::sleep(Duration::from_secs(
+                options.interval_secs.max(MIN_INTERVAL_SECS),
+            ))
+            .await;
+            let session = core::current_session().filter(|_| options.enabled);
+            let Some((_, config_path)) = session else {
+                reset();
+                continue;
+            };
+            if let Err(e) = probe(&app, &options, &config_path, &mut last_switch).await {
+                log::warn!("[failover] {}", e);
+            }
+        }
+    });
+}
+
+/// 获取自动切换的监控状态
+#[tauri::command]
+pub fn get_failover_status() -> FailoverStatus {
+    STATUS.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{HttpServer, Response};
+
+    fn result(tag: &str, delay_ms: Option<u16>, tested_at: i64) -> LatencyResult {
+        LatencyResult {
+            tag: tag.to_string(),
+            delay_ms,
+            error: None,
+            test_url: clash_api::DEFAULT_DELAY_URL.to_string(),
+            tested_at,
+        }
+    }
+
+    fn names(tags: &[&str]) -> Vec<String> {
+        tags.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn tags(candidates: &[(String, u16)]) -> Vec<&str> {
+        candidates.iter().map(|(tag, _)| tag.as_str()).collect()
+    }
+
+    #[test]
+    fn prefers_priority_list_then_lowest_delay() {
+        let members = names(&["HK", "JP", "SG", "US"]);
+        let results = vec![
+            result("SG", Some(40), 100),
+            result("JP", Some(90), 100),
+            result("US", None, 100),
+            result("HK", Some(10), 100),
+            // 不在选择器中的节点
+            result("KR", Some(5), 100),
+        ];
+
+        assert_eq!(
+            rank_candidates("HK", &members, &results, &names(&["US", "JP"]), 0),
+            vec![("JP".to_string(), 90), ("SG".to_string(), 40)]
+        );
+        assert_eq!(
+            tags(&rank_candidates("HK", &members, &results, &[], 0)),
+            vec!["SG", "JP"]
+        );
+        // 优先列表中的节点都不可用
+        assert_eq!(
+            tags(&rank_candidates(
+                "SG",
+                &members,
+                &results,
+                &names(&["US", "KR"]),
+                0
+            )),
+            vec!["HK", "JP"]
+        );
+        assert!(rank_candidates("HK", &names(&["HK", "US"]), &results, &[], 0).is_empty());
+    }
+
+    #[test]
+    fn ignores_stale_results() {
+        let members = names(&["HK", "JP", "SG"]);
+        let results = vec![result("JP", Some(20), 1_000), result("SG", Some(80), 5_000)];
+        assert_eq!(
+            tags(&rank_candidates(
+                "HK",
+                &members,
+                &results,
+                &names(&["JP"]),
+                4_000
+            )),
+            vec!["SG"]
+        );
+        assert!(rank_candidates("HK", &members, &results, &[], 6_000).is_empty());
+    }
+
+    #[tokio::test]
+    async fn probes_members_when_results_are_stale() {
+        let server = HttpServer::start(|line| async move {
+            if line.starts_with("GET /proxies/JP/delay") {
+                Response::json("200 OK", r#"{"delay":90}"#)
+            } else if line.starts_with("GET /proxies/US/delay") {
+                Response::json("200 OK", r#"{"delay":40}"#)
+            } else {
+                Response::json("504 Gateway Timeout", r#"{"message":"Timeout"}"#)
+            }
+        })
+        .await;
+        let members = names(&["HK", "JP", "SG", "US"]);
+        let stale = vec![result("JP", Some(20), 1_000)];
+        assert!(rank_candidates("HK", &members, &stale, &[], 4_000).is_empty());
+        assert!(rank_candidates("HK", &members, &[], &[], 0).is_empty());
+
+        let options = FailoverOptions::default();
+        let fresh = refresh_results(&server.clash_api(""), "HK", &members, &options, 2).await;
+        assert_eq!(fresh.len(), 3);
+        assert!(!server
+            .requests()
+            .iter()
+            .any(|r| r.starts_with("GET /proxies/HK/")));
+        assert_eq!(
+            rank_candidates("HK", &members, &fresh, &[], 4_000),
+            vec![("US".to_string(), 40), ("JP".to_string(), 90)]
+        );
+        assert_eq!(
+            tags(&rank_candidates(
+                "HK",
+                &members,
+                &fresh,
+                &names(&["JP"]),
+                4_000
+            )),
+            vec!["JP", "US"]
+        );
+    }
+}
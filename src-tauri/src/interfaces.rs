@@ -0,0 +1,325 @@
+use serde::Serialize;
+
+/// 网卡上的一个 IP 地址
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct InterfaceAddress {
+    pub address: String,
+    pub prefix: u8,
+}
+
+/// 本机网卡信息
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct NetworkInterface {
+    pub name: String,
+    pub ipv4: Vec<InterfaceAddress>,
+    pub ipv6: Vec<InterfaceAddress>,
+    pub mac: Option<String>,
+    pub is_up: bool,
+    pub is_running: bool,
+    pub is_loopback: bool,
+    /// 默认路由是否经由该网卡
+    pub is_default: bool,
+    /// 虚拟网卡（网桥、隧道、容器、虚拟机等）
+    pub is_virtual: bool,
+}
+
+// 常见虚拟网卡名称前缀
+#[cfg_attr(target_os = "windows", allow(dead_code))]
+const VIRTUAL_PREFIXES: [&str; 20] = [
+    "docker",
+    "br-",
+    "veth",
+    "virbr",
+    "vmnet",
+    "vboxnet",
+    "podman",
+    "cni",
+    "flannel",
+    "tun",
+    "tap",
+    "wg",
+    "tailscale",
+    "zt",
+    "utun",
+    "bridge",
+    "awdl",
+    "llw",
+    "gif",
+    "stf",
+];
+
+#[cfg_attr(target_os = "windows", allow(dead_code))]
+fn is_virtual_name(name: &str) -> bool {
+    VIRTUAL_PREFIXES
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+}
+
+#[cfg(unix)]
+mod native {
+    use super::{InterfaceAddress, NetworkInterface};
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    fn format_mac(bytes: &[u8]) -> Option<String> {
+        if bytes.len() != 6 || bytes.iter().all(|b| *b == 0) {
+            return None;
+        }
+        Some(
+            bytes
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<Vec<_>>()
+                .join(":"),
+        )
+    }
+
+    #[cfg(target_os = "linux")]
+    unsafe fn link_mac(addr: *const libc::sockaddr) -> Option<String> {
+        let ll = &*(addr as *const libc::sockaddr_ll);
+        let len = (ll.sll_halen as usize).min(ll.sll_addr.len());
+        format_mac(&ll.sll_addr[..len])
+    }
+
+    #[cfg(target_os = "macos")]
+    unsafe fn link_mac(addr: *const libc::sockaddr) -> Option<String> {
+        let dl = &*(addr as *const libc::sockaddr_dl);
+        let data = dl.sdl_data.as_ptr() as *const u8;
+        let bytes =
+            std::slice::from_raw_parts(data.add(dl.sdl_nlen as usize), dl.sdl_alen as usize);
+        format_mac(bytes)
+    }
+
+    #[cfg(target_os = "linux")]
+    const LINK_FAMILY: i32 = libc::AF_PACKET;
+    #[cfg(target_os = "macos")]
+    const LINK_FAMILY: i32 = libc::AF_LINK;
+
+    fn interface_mut<'a>(
+        interfaces: &'a mut Vec<NetworkInterface>,
+        name: &str,
+        flags: u32,
+    ) -> &'a mut NetworkInterface {
+        // getifaddrs 对每个地址返回一项，按网卡名称合并并保持原有顺序
+        let index = match interfaces.iter().position(|i| i.name == name) {
+            Some(index) => index,
+            None => {
+                interfaces.push(NetworkInterface {
+                    name: name.to_string(),
+                    ipv4: Vec::new(),
+                    ipv6: Vec::new(),
+                    mac: None,
+                    is_up: flags & libc::IFF_UP as u32 != 0,
+                    is_running: flags & libc::IFF_RUNNING as u32 != 0,
+                    is_loopback: flags & libc::IFF_LOOPBACK as u32 != 0,
+                    is_default: false,
+                    is_virtual: false,
+                });
+                interfaces.len() - 1
+            }
+        };
+        &mut interfaces[index]
+    }
+
+    /// 通过 getifaddrs 枚举网卡
+    pub fn enumerate() -> std::io::Result<Vec<NetworkInterface>> {
+        let mut interfaces: Vec<NetworkInterface> = Vec::new();
+        let mut ifap: *mut libc::ifaddrs = std::ptr::null_mut();
+        if unsafe { libc::getifaddrs(&mut ifap) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        let mut cursor = ifap;
+        while !cursor.is_null() {
+            let ifa = unsafe { &*cursor };
+            cursor = ifa.ifa_next;
+            if ifa.ifa_name.is_null() {
+                continue;
+            }
+            let name = unsafe { std::ffi::CStr::from_ptr(ifa.ifa_name) }
+                .to_string_lossy()
+                .into_owned();
+            let interface = interface_mut(&mut interfaces, &name, ifa.ifa_flags);
+            if ifa.ifa_addr.is_null() {
+                continue;
+            }
+
+            let family = unsafe { (*ifa.ifa_addr).sa_family } as i32;
+            match family {
+                libc::AF_INET => {
+                    let addr = unsafe { &*(ifa.ifa_addr as *const libc::sockaddr_in) };
+                    let prefix = if ifa.ifa_netmask.is_null() {
+                        32
+                    } else {
+                        let mask = unsafe { &*(ifa.ifa_netmask as *const libc::sockaddr_in) };
+                        u32::from_be(mask.sin_addr.s_addr).count_ones() as u8
+                    };
+                    interface.ipv4.push(InterfaceAddress {
+                        address: Ipv4Addr::from(u32::from_be(addr.sin_addr.s_addr)).to_string(),
+                        prefix,
+                    });
+                }
+                libc::AF_INET6 => {
+                    let addr = unsafe { &*(ifa.ifa_addr as *const libc::sockaddr_in6) };
+                    let prefix = if ifa.ifa_netmask.is_null() {
+                        128
+                    } else {
+                        let mask = unsafe { &*(ifa.ifa_netmask as *const libc::sockaddr_in6) };
+                        mask.sin6_addr
+                            .s6_addr
+                            .iter()
+                            .map(|b| b.count_ones())
+                            .sum::<u32>() as u8
+                    };
+                    interface.ipv6.push(InterfaceAddress {
+                        address: Ipv6Addr::from(addr.sin6_addr.s6_addr).to_string(),
+                        prefix,
+                    });
+                }
+                f if f == LINK_FAMILY => {
+                    interface.mac = unsafe { link_mac(ifa.ifa_addr) };
+                }
+                _ => {}
+            }
+        }
+        unsafe { libc::freeifaddrs(ifap) };
+        Ok(interfaces)
+    }
+}
+
+/// 默认路由所在的网卡名称
+#[cfg(target_os = "linux")]
+fn default_interfaces() -> Vec<String> {
+    let mut names = Vec::new();
+    // /proc/net/route: Iface Destination Gateway ...，默认路由的 Destination 为 00000000
+    if let Ok(content) = std::fs::read_to_string("/proc/net/route") {
+        for line in content.lines().skip(1) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() > 1
+                && fields[1] == "00000000"
+                && !names.contains(&fields[0].to_string())
+            {
+                names.push(fields[0].to_string());
+            }
+        }
+    }
+    // /proc/net/ipv6_route: 目标地址、前缀长度 ... 网卡名称在最后一列
+    if let Ok(content) = std::fs::read_to_string("/proc/net/ipv6_route") {
+        for line in content.lines() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() == 10
+                && fields[0].chars().all(|c| c == '0')
+                && fields[1] == "00"
+                && fields[9] != "lo"
+                && !names.contains(&fields[9].to_string())
+            {
+                names.push(fields[9].to_string());
+            }
+        }
+    }
+    names
+}
+
+/// 默认路由所在的网卡名称
+#[cfg(target_os = "macos")]
+fn default_interfaces() -> Vec<String> {
+    let mut names = Vec::new();
+    for family in ["-inet", "-inet6"] {
+        let Ok(output) = std::process::Command::new("route")
+            .args(["-n", "get", family, "default"])
+            .output()
+        else {
+            continue;
+        };
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let name = stdout
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("interface:"))
+            .map(|name| name.trim().to_string());
+        if let Some(name) = name {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+    names
+}
+
+/// 默认网关
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct DefaultGateway {
+    pub interface: String,
+    pub address: String,
+    /// 网关的 MAC 地址（来自 ARP 表）
+    pub mac: Option<String>,
+}
+
+/// 读取 IPv4 默认网关及其 MAC 地址
+#[cfg(target_os = "linux")]
+pub fn default_gateway() -> Option<DefaultGateway> {
+    let routes = std::fs::read_to_string("/proc/net/route").ok()?;
+    let (interface, address) = routes.lines().skip(1).find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 3 || fields[1] != "00000000" {
+            return None;
+        }
+        // 网关地址为小端序十六进制
+        let gateway = u32::from_str_radix(fields[2], 16).ok()?;
+        let address = std::net::Ipv4Addr::from(gateway.to_le_bytes());
+        Some((fields[0].to_string(), address.to_string()))
+    })?;
+
+    // /proc/net/arp: IP address, HW type, Flags, HW address, Mask, Device
+    let mac = std::fs::read_to_string("/proc/net/arp")
+        .ok()
+        .and_then(|arp| {
+            arp.lines().skip(1).find_map(|line| {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                (fields.len() >= 6 && fields[0] == address && fields[5] == interface)
+                    .then(|| fields[3].to_lowercase())
+            })
+        })
+        .filter(|mac| mac != "00:00:00:00:00:00");
+
+    Some(DefaultGateway {
+        interface,
+        address,
+        mac,
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn is_virtual(name: &str) -> bool {
+    // 物理网卡在 sysfs 中链接到具体设备，虚拟网卡链接到 /sys/devices/virtual
+    std::fs::read_link(format!("/sys/class/net/{}", name))
+        .map(|target| target.to_string_lossy().contains("/virtual/"))
+        .unwrap_or(false)
+        || is_virtual_name(name)
+}
+
+#[cfg(target_os = "macos")]
+fn is_virtual(name: &str) -> bool {
+    is_virtual_name(name)
+}
+
+/// 枚举本机所有网卡
+#[cfg(unix)]
+pub fn list() -> Result<Vec<NetworkInterface>, String> {
+    let mut interfaces = native::enumerate().map_err(|e| e.to_string())?;
+    let defaults = default_interfaces();
+    for interface in interfaces.iter_mut() {
+        interface.is_default = defaults.contains(&interface.name);
+        interface.is_virtual = !interface.is_loopback && is_virtual(&interface.name);
+    }
+    Ok(interfaces)
+}
+
+#[cfg(target_os = "windows")]
+pub fn list() -> Result<Vec<NetworkInterface>, String> {
+    Err("Interface enumeration is not supported on Windows yet".to_string())
+}
+
+/// 列出本机所有网卡及其地址、状态
+#[tauri::command]
+pub fn list_network_interfaces() -> Result<Vec<NetworkInterface>, String> {
+    list()
+}
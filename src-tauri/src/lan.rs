@@ -1,9 +1,10 @@
-use crate::core::stop;
-use tauri::{
-    http::{header::LOCATION, StatusCode},
-    AppHandle,
-};
-use tauri_plugin_http::reqwest::{self, redirect::Policy};
+use crate::captive::{self, CaptiveStatus};
+use crate::core::{self, stop};
+#[cfg(unix)]
+use crate::interfaces;
+use tauri::AppHandle;
+use tauri_plugin_http::reqwest;
+#[cfg(target_os = "windows")]
 use tokio::process::Command;
 use webbrowser;
 
@@ -34,15 +35,6 @@ fn is_private_ip(ip: &str) -> bool {
     false
 }
 
-fn build_no_redirect_client() -> reqwest::Client {
-    reqwest::ClientBuilder::new()
-        .timeout(std::time::Duration::from_secs(10))
-        .redirect(Policy::none())
-        .no_proxy()
-        .build()
-        .unwrap()
-}
-
 #[tauri::command]
 pub async fn get_lan_ip() -> Result<String, String> {
     #[cfg(target_os = "windows")]
@@ -71,68 +63,45 @@ pub async fn get_lan_ip() -> Result<String, String> {
     }
     #[cfg(target_os = "linux")]
     {
-        let output = Command::new("bash")
-            .arg("-c")
-            .arg("ip -4 addr show | awk '/inet /{print $2}' | cut -d/ -f1 | grep -v '^127\\.' | head -n 1")
-            .output()
-            .await
-            .map_err(|e| e.to_string())?;
-        let ip = String::from_utf8_lossy(&output.stdout);
-        Ok(ip.trim().to_string())
+        // 第一个非回环的 IPv4 地址
+        let interfaces = interfaces::list()?;
+        let ip = interfaces
+            .iter()
+            .flat_map(|interface| interface.ipv4.iter())
+            .map(|addr| addr.address.clone())
+            .find(|ip| !ip.starts_with("127."))
+            .unwrap_or_default();
+        Ok(ip)
     }
     #[cfg(target_os = "macos")]
     {
-        let output = Command::new("bash")
-            .arg("-c")
-            .arg("ifconfig")
-            .output()
-            .await
-            .map_err(|e| e.to_string())?;
-
-        let ifconfig_output = String::from_utf8_lossy(&output.stdout);
-
-        // 解析ifconfig输出，查找最合适的局域网IP
+        // 解析网卡列表，查找最合适的局域网IP
+        let interfaces = interfaces::list()?;
         let mut best_ip: Option<String> = None;
-        let mut current_interface = String::new();
-        let mut is_up = false;
-        let mut is_running = false;
-
-        for line in ifconfig_output.lines() {
-            // 检测新的网络接口
-            if !line.starts_with('\t') && !line.starts_with(' ') && line.contains(':') {
-                if let Some(interface) = line.split(':').next() {
-                    current_interface = interface.to_string();
-                    is_up = line.contains("UP");
-                    is_running = line.contains("RUNNING");
-                }
-            }
 
-            // 查找inet地址
-            if line.trim().starts_with("inet ") && is_up && is_running {
-                let parts: Vec<&str> = line.trim().split_whitespace().collect();
-                if parts.len() >= 2 {
-                    let ip = parts[1];
+        for interface in interfaces.iter().filter(|i| i.is_up && i.is_running) {
+            for addr in &interface.ipv4 {
+                let ip = addr.address.as_str();
 
-                    // 跳过回环地址
-                    if ip.starts_with("127.") {
-                        continue;
-                    }
+                // 跳过回环地址
+                if ip.starts_with("127.") {
+                    continue;
+                }
 
-                    // 跳过链路本地地址
-                    if ip.starts_with("169.254.") {
-                        continue;
-                    }
+                // 跳过链路本地地址
+                if ip.starts_with("169.254.") {
+                    continue;
+                }
 
-                    // 检查是否为私有网络地址
-                    if is_private_ip(ip) {
-                        // 优先级：en0 (以太网/WiFi) > en1 > 其他接口
-                        if current_interface == "en0" {
-                            return Ok(ip.to_string());
-                        } else if current_interface.starts_with("en") && best_ip.is_none() {
-                            best_ip = Some(ip.to_string());
-                        } else if best_ip.is_none() {
-                            best_ip = Some(ip.to_string());
-                        }
+                // 检查是否为私有网络地址
+                if is_private_ip(ip) {
+                    // 优先级：en0 (以太网/WiFi) > en1 > 其他接口
+                    if interface.name == "en0" {
+                        return Ok(ip.to_string());
+                    } else if interface.name.starts_with("en") && best_ip.is_none() {
+                        best_ip = Some(ip.to_string());
+                    } else if best_ip.is_none() {
+                        best_ip = Some(ip.to_string());
                     }
                 }
             }
@@ -143,12 +112,20 @@ pub async fn get_lan_ip() -> Result<String, String> {
 }
 
 #[tauri::command]
-pub async fn open_browser(app: AppHandle, url: String) -> Result<(), String> {
+pub async fn open_browser(app: AppHandle) -> Result<(), String> {
     // zh:需要网络认证，尝试停止和重置代理。
     // en: Network authentication required, try to stop and reset the proxy.
-    stop(app).await.unwrap_or_else(|e| {
+    let session = core::current_session();
+    stop(app.clone()).await.unwrap_or_else(|e| {
         log::error!("Failed to stop app: {}", e);
     });
+    // 认证完成后以原来的模式恢复代理
+    if let Some((mode, path)) = session {
+        captive::resume::start(&app, mode, path);
+    }
+
+    // 代理停止后再探测，TUN 模式下探测请求才不会经过隧道
+    let url = get_captive_redirect_url(app.clone()).await;
 
     // 使用 webbrowser 库打开浏览器
     // zh: 如果有重定向，则打开浏览器并返回 false
@@ -160,54 +137,22 @@ pub async fn open_browser(app: AppHandle, url: String) -> Result<(), String> {
 }
 
 #[tauri::command]
-pub async fn check_captive_portal_status() -> i8 {
+pub async fn check_captive_portal_status(app: AppHandle) -> i8 {
     // -1 代表无法访问, 0 代表可以访问, 1 代表需要认证
-
-    // 如果需要替换其他检测地址，务必满足以下条件
-    // - 中国大陆以及海外可访问
-    // - 支持无重定向的 http 协议
-    // - 解析记录仅 IPv4，如果有 IPv6 记录可能在纯 IPv4 网络下失败导致误报。
-
-    let url = "http://captive.apple.com/";
-
-    let client = build_no_redirect_client();
-    match client.get(url).send().await {
-        Ok(response) => {
-            let status = response.status();
-            if status == StatusCode::OK {
-                0
-            } else if status.is_redirection() {
-                1
-            } else {
-                log::error!("Unexpected status code: {}", status);
-                -1
-            }
-        }
-        Err(_) => -1,
+    // 详细结果见 captive::detect_captive_portal
+    match captive::detect(&app).await.status {
+        CaptiveStatus::Online => 0,
+        CaptiveStatus::Portal => 1,
+        CaptiveStatus::Offline => -1,
     }
 }
 
 #[tauri::command]
-pub async fn get_captive_redirect_url() -> String {
-    let client = build_no_redirect_client();
-
-    match client.get(DEFAULT_CAPTIVE_URL).send().await {
-        Ok(response) => {
-            let status = response.status();
-            if status.is_redirection() {
-                response
-                    .headers()
-                    .get(LOCATION)
-                    .and_then(|h| h.to_str().ok())
-                    .map(|s| s.to_string())
-                    .unwrap_or_else(|| DEFAULT_CAPTIVE_URL.to_string())
-            } else {
-                log::error!("Unexpected status code: {}", status);
-                DEFAULT_CAPTIVE_URL.to_string()
-            }
-        }
-        Err(_) => DEFAULT_CAPTIVE_URL.to_string(),
-    }
+pub async fn get_captive_redirect_url(app: AppHandle) -> String {
+    captive::detect(&app)
+        .await
+        .portal_url
+        .unwrap_or_else(|| DEFAULT_CAPTIVE_URL.to_string())
 }
 
 #[tauri::command]
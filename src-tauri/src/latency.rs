@@ -0,0 +1,403 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{Row, SqlitePool};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_store::StoreExt;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::clash_api::{self, ClashApi};
+use crate::core;
+use crate::database;
+
+const LATENCY_OPTIONS_STORE_KEY: &str = "latency_test_options_key";
+// 只保留最近 30 天的记录
+const HISTORY_RETENTION_SECS: i64 = 30 * 24 * 3600;
+// 策略组与非代理出站不参与测试
+const SKIPPED_TYPES: [&str; 5] = ["selector", "urltest", "direct", "block", "dns"];
+
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// 批量延迟测试参数
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LatencyTestOptions {
+    pub url: String,
+    pub timeout_ms: u64,
+    /// 同时测试的节点数
+    pub concurrency: usize,
+}
+
+impl Default for LatencyTestOptions {
+    fn default() -> Self {
+        Self {
+            url: clash_api::DEFAULT_DELAY_URL.to_string(),
+            timeout_ms: clash_api::DEFAULT_DELAY_TIMEOUT_MS,
+            concurrency: 8,
+        }
+    }
+}
+
+/// 单个节点的测试结果，也是 latency-test-result 事件内容
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct LatencyResult {
+    pub tag: String,
+    /// 失败时为空
+    pub delay_ms: Option<u16>,
+    pub error: Option<String>,
+    pub test_url: String,
+    /// 测试时间（Unix 秒）
+    pub tested_at: i64,
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// 配置中的代理节点
+fn proxy_outbounds(config: &Value) -> Vec<String> {
+    config["outbounds"]
+        .as_array()
+        .map(|outbounds| {
+            outbounds
+                .iter()
+                .filter(|o| !SKIPPED_TYPES.contains(&o["type"].as_str().unwrap_or_default()))
+                .filter_map(|o| o["tag"].as_str().map(|s| s.to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 按延迟从低到高排序，失败的节点排在最后
+pub fn sort_by_delay(results: &mut [LatencyResult]) {
+    results.sort_by_key(|r| (r.delay_ms.is_none(), r.delay_ms, r.tag.clone()));
+}
+
+/// 并发测试所有节点，每完成一个回调一次
+pub async fn test_all<F: FnMut(&LatencyResult)>(
+    api: &ClashApi,
+    tags: Vec<String>,
+    options: &LatencyTestOptions,
+    mut on_result: F,
+) -> Vec<LatencyResult> {
+    let semaphore = Arc::new(Semaphore::new(options.concurrency.max(1)));
+    let mut set = JoinSet::new();
+    for tag in tags {
+        let api = api.clone();
+        let semaphore = semaphore.clone();
+        let url = options.url.clone();
+        let timeout_ms = options.timeout_ms;
+        set.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            let result = api.delay(&tag, &url, timeout_ms).await;
+            LatencyResult {
+                tag,
+                delay_ms: result.as_ref().ok().copied(),
+                error: result.err().map(|e| e.to_string()),
+                test_url: url,
+                tested_at: now(),
+            }
+        });
+    }
+
+    let mut results = Vec::new();
+    while let Some(joined) = set.join_next().await {
+        if let Ok(result) = joined {
+            on_result(&result);
+            results.push(result);
+        }
+    }
+    sort_by_delay(&mut results);
+    results
+}
+
+/// 保存测试结果并清理过期记录
+pub async fn save(
+    pool: &SqlitePool,
+    identifier: &str,
+    results: &[LatencyResult],
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    for result in results {
+        sqlx::query(
+            "INSERT INTO latency_results (identifier, tag, delay_ms, error, test_url, tested_at)
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(identifier)
+        .bind(&result.tag)
+        .bind(result.delay_ms)
+        .bind(&result.error)
+        .bind(&result.test_url)
+        .bind(result.tested_at)
+        .execute(&mut *tx)
+        .await?;
+    }
+    sqlx::query("DELETE FROM latency_results WHERE tested_at < ?")
+        .bind(now() - HISTORY_RETENTION_SECS)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await
+}
+
+fn from_rows(rows: Vec<sqlx::sqlite::SqliteRow>) -> Result<Vec<LatencyResult>, sqlx::Error> {
+    rows.into_iter()
+        .map(|row| {
+            Ok(LatencyResult {
+                tag: row.try_get("tag")?,
+                delay_ms: row.try_get("delay_ms")?,
+                error: row.try_get("error")?,
+                test_url: row.try_get("test_url")?,
+                tested_at: row.try_get("tested_at")?,
+            })
+        })
+        .collect()
+}
+
+/// 每个节点最近一次的结果，按延迟排序
+pub async fn latest(
+    pool: &SqlitePool,
+    identifier: &str,
+) -> Result<Vec<LatencyResult>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT tag, delay_ms, error, test_url, tested_at FROM latency_results r
+         WHERE identifier = ?1 AND id = (
+             SELECT MAX(id) FROM latency_results WHERE identifier = ?1 AND tag = r.tag
+         )",
+    )
+    .bind(identifier)
+    .fetch_all(pool)
+    .await?;
+    let mut results = from_rows(rows)?;
+    sort_by_delay(&mut results);
+    Ok(results)
+}
+
+/// 单个节点的历史记录，最新的在前
+pub async fn history(
+    pool: &SqlitePool,
+    identifier: &str,
+    tag: &str,
+    limit: u32,
+) -> Result<Vec<LatencyResult>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT tag, delay_ms, error, test_url, tested_at FROM latency_results
+         WHERE identifier = ? AND tag = ? ORDER BY tested_at DESC, id DESC LIMIT ?",
+    )
+    .bind(identifier)
+    .bind(tag)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    from_rows(rows)
+}
+
+/// 读取测试参数
+pub fn options(app: &AppHandle) -> LatencyTestOptions {
+    app.store("settings.json")
+        .ok()
+        .and_then(|store| store.get(LATENCY_OPTIONS_STORE_KEY))
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
+}
+
+async fn run(app: &AppHandle, options: &LatencyTestOptions) -> Result<Vec<LatencyResult>, String> {
+    let (_, config_path) = core::current_session().ok_or("Proxy is not running")?;
+    let config: Value = std::fs::read_to_string(&config_path)
+        .map_err(|e| e.to_string())
+        .and_then(|content| serde_json::from_str(&content).map_err(|e| e.to_string()))?;
+    let tags = proxy_outbounds(&config);
+
+    let api = ClashApi::current(app);
+    let results = test_all(&api, tags, options, |result| {
+        if let Err(e) = app.emit("latency-test-result", result) {
+            log::error!("Failed to emit latency-test-result event: {}", e);
+        }
+    })
+    .await;
+
+    let identifier = database::selected_subscription(app).unwrap_or_default();
+    match database::pool(app).await {
+        Ok(pool) => {
+            if let Err(e) = save(pool, &identifier, &results).await {
+                log::error!("[latency] Failed to save results: {}", e);
+            }
+        }
+        Err(e) => log::error!("[latency] Failed to open database: {}", e),
+    }
+    Ok(results)
+}
+
+/// 测试当前配置中所有节点的延迟，每完成一个节点发送 latency-test-result 事件
+#[tauri::command]
+pub async fn run_latency_test(
+    app: AppHandle,
+    options: Option<LatencyTestOptions>,
+) -> Result<Vec<LatencyResult>, String> {
+    let options = options.unwrap_or_else(|| self::options(&app));
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        return Err("A latency test is already running".to_string());
+    }
+    let results = run(&app, &options).await;
+    RUNNING.store(false, Ordering::SeqCst);
+    let results = results?;
+    log::info!(
+        "[latency] Tested {} nodes, {} reachable",
+        results.len(),
+        results.iter().filter(|r| r.delay_ms.is_some()).count()
+    );
+    Ok(results)
+}
+
+/// 当前订阅下每个节点最近一次的结果
+#[tauri::command]
+pub async fn get_latest_latency(app: AppHandle) -> Result<Vec<LatencyResult>, String> {
+    let identifier = database::selected_subscription(&app).unwrap_or_default();
+    let pool = database::pool(&app).await.map_err(|e| e.to_string())?;
+    latest(pool, &identifier).await.map_err(|e| e.to_string())
+}
+
+/// 当前订阅下单个节点的历史记录
+#[tauri::command]
+pub async fn get_latency_history(
+    app: AppHandle,
+    tag: String,
+    limit: Option<u32>,
+) -> Result<Vec<LatencyResult>, String> {
+    let identifier = database::selected_subscription(&app).unwrap_or_default();
+    let pool = database::pool(&app).await.map_err(|e| e.to_string())?;
+    history(pool, &identifier, &tag, limit.unwrap_or(50))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{HttpServer, Response};
+    use serde_json::json;
+    use sqlx::sqlite::SqlitePoolOptions;
+    use std::sync::atomic::AtomicUsize;
+    use std::time::Duration;
+
+    fn result(tag: &str, delay_ms: Option<u16>, tested_at: i64) -> LatencyResult {
+        LatencyResult {
+            tag: tag.to_string(),
+            delay_ms,
+            error: delay_ms.is_none().then(|| "timeout".to_string()),
+            test_url: clash_api::DEFAULT_DELAY_URL.to_string(),
+            tested_at,
+        }
+    }
+
+    #[test]
+    fn skips_groups_and_builtin_outbounds() {
+        let config = json!({"outbounds": [
+            {"type": "selector", "tag": "ExitGateway"},
+            {"type": "urltest", "tag": "auto"},
+            {"type": "direct", "tag": "direct"},
+            {"type": "vmess", "tag": "HK 01"},
+            {"type": "shadowsocks", "tag": "JP 01"},
+        ]});
+        assert_eq!(proxy_outbounds(&config), vec!["HK 01", "JP 01"]);
+    }
+
+    #[tokio::test]
+    async fn limits_concurrency_against_mock_api() {
+        let active = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let server = {
+            let (active, peak) = (active.clone(), peak.clone());
+            HttpServer::start(move |line| {
+                let (active, peak) = (active.clone(), peak.clone());
+                async move {
+                    let count = active.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(count, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    active.fetch_sub(1, Ordering::SeqCst);
+                    if line.starts_with("GET /proxies/dead/delay") {
+                        Response::json("504 Gateway Timeout", r#"{"message":"Timeout"}"#)
+                    } else {
+                        Response::json("200 OK", r#"{"delay":42}"#)
+                    }
+                }
+            })
+            .await
+        };
+
+        let api = server.clash_api("");
+        let tags: Vec<String> = ["a", "b", "c", "d", "dead", "e"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let options = LatencyTestOptions {
+            concurrency: 2,
+            ..Default::default()
+        };
+        let mut streamed = 0;
+        let results = test_all(&api, tags, &options, |_| streamed += 1).await;
+
+        assert_eq!(streamed, 6);
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+        assert_eq!(results.len(), 6);
+        assert_eq!(results[0].delay_ms, Some(42));
+        let dead = results.last().unwrap();
+        assert_eq!(dead.tag, "dead");
+        assert!(dead.error.as_deref().unwrap().contains("Timeout"));
+    }
+
+    #[tokio::test]
+    async fn persists_latest_and_history() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        database::init_schema(&pool).await.unwrap();
+        let t = now();
+        save(
+            &pool,
+            "sub",
+            &[
+                result("HK", Some(300), t - 60),
+                result("JP", Some(80), t - 60),
+            ],
+        )
+        .await
+        .unwrap();
+        save(
+            &pool,
+            "sub",
+            &[result("HK", Some(120), t), result("JP", None, t)],
+        )
+        .await
+        .unwrap();
+        save(&pool, "other", &[result("US", Some(10), t)])
+            .await
+            .unwrap();
+        // 过期记录在下次保存时清理
+        save(
+            &pool,
+            "sub",
+            &[result("SG", Some(50), t - HISTORY_RETENTION_SECS - 1)],
+        )
+        .await
+        .unwrap();
+
+        let latest = latest(&pool, "sub").await.unwrap();
+        let tags: Vec<_> = latest
+            .iter()
+            .map(|r| (r.tag.as_str(), r.delay_ms))
+            .collect();
+        assert_eq!(tags, vec![("HK", Some(120)), ("JP", None)]);
+
+        let history = history(&pool, "sub", "HK", 10).await.unwrap();
+        let delays: Vec<_> = history.iter().map(|r| r.delay_ms).collect();
+        assert_eq!(delays, vec![Some(120), Some(300)]);
+    }
+}
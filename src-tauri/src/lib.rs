@@ -4,11 +4,26 @@ use std::fs;
 use tauri::{AppHandle, Manager, Window, WindowEvent};
 use tauri_plugin_http::reqwest;
 mod app_status;
+mod captive;
+mod clash_api;
 mod core;
 mod database;
+mod diagnostics;
+mod failover;
+mod interfaces;
 mod lan;
+mod latency;
+#[cfg(target_os = "linux")]
+mod network_monitor;
+#[cfg(target_os = "linux")]
+mod network_profiles;
+mod pac;
 mod plugins;
 mod privilege;
+mod selection;
+mod speed_test;
+#[cfg(test)]
+mod test_support;
 mod vpn;
 
 #[tauri::command]
@@ -162,18 +177,80 @@ pub fn run() {
             get_app_version,
             get_tray_icon,
             lan::get_lan_ip,
+            interfaces::list_network_interfaces,
+            #[cfg(target_os = "linux")]
+            network_profiles::get_network_profiles,
+            #[cfg(target_os = "linux")]
+            network_profiles::set_network_profiles,
+            #[cfg(target_os = "linux")]
+            network_profiles::get_current_network,
+            #[cfg(target_os = "linux")]
+            network_profiles::get_active_network_profile,
             lan::ping_google,
             lan::open_browser,
             lan::get_captive_redirect_url,
             lan::check_captive_portal_status,
+            captive::detect_captive_portal,
+            captive::resume::cancel_captive_resume,
+            captive::resume::is_captive_resume_pending,
+            diagnostics::run_connectivity_diagnostics,
+            diagnostics::check_exit_ip,
+            diagnostics::run_dns_leak_test,
+            diagnostics::check_clock_skew,
+            speed_test::run_speed_test,
+            speed_test::cancel_speed_test,
+            clash_api::get_clash_endpoint,
+            clash_api::get_clash_version,
+            clash_api::get_clash_proxies,
+            clash_api::get_clash_proxy,
+            clash_api::select_clash_proxy,
+            clash_api::test_clash_proxy_delay,
+            clash_api::test_clash_group_delay,
+            clash_api::get_clash_connections,
+            clash_api::list_clash_connections,
+            clash_api::close_clash_connection,
+            clash_api::close_clash_connections,
+            clash_api::close_all_clash_connections,
+            clash_api::get_clash_traffic,
+            clash_api::get_clash_memory,
+            clash_api::get_clash_configs,
+            clash_api::patch_clash_configs,
+            latency::run_latency_test,
+            latency::get_latest_latency,
+            latency::get_latency_history,
+            failover::get_failover_status,
             core::stop,
             core::start,
             core::version,
             core::is_running,
             core::reload_config,
             app_status::read_logs,
+            app_status::get_clock_skew_status,
             privilege::is_privileged,
             privilege::save_privilege_password_to_keyring,
+            vpn::bypass::get_proxy_bypass,
+            vpn::bypass::set_proxy_bypass,
+            vpn::guard::get_proxy_conflicts,
+            vpn::guard::get_system_proxy_guard_action,
+            vpn::guard::set_system_proxy_guard_action,
+            #[cfg(target_os = "linux")]
+            vpn::gateway::get_gateway_status,
+            #[cfg(target_os = "linux")]
+            vpn::bridges::get_bridge_interfaces,
+            #[cfg(target_os = "linux")]
+            vpn::bridges::set_bridge_interface_excluded,
+            #[cfg(target_os = "linux")]
+            vpn::tun_filters::get_tun_filter_rules,
+            #[cfg(target_os = "linux")]
+            vpn::tun_filters::set_tun_filter_rules,
+            #[cfg(target_os = "linux")]
+            vpn::tun_filters::get_tun_filter_capabilities,
+            #[cfg(target_os = "linux")]
+            vpn::tun_filters::list_local_users,
+            #[cfg(target_os = "linux")]
+            vpn::tun_filters::list_local_processes,
+            #[cfg(target_os = "linux")]
+            vpn::tun_diagnostics::get_tun_diagnostics,
         ])
         .setup(|app| {
             #[cfg(desktop)]
@@ -194,6 +271,20 @@ pub fn run() {
                 log::error!("Failed to copy database files: {}", e);
             }
 
+            // 监听网卡、地址与路由变化
+            #[cfg(target_os = "linux")]
+            network_monitor::start(app.handle());
+
+            // 上次会话异常退出时，恢复用户原始的系统代理配置
+            let recover_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                vpn::snapshot::recover(&recover_handle).await;
+                #[cfg(target_os = "linux")]
+                vpn::gateway::recover(&recover_handle).await;
+                #[cfg(target_os = "linux")]
+                vpn::resolver::recover(&recover_handle).await;
+            });
+
             let app_version = app.package_info().version.to_string();
             let os = tauri_plugin_os::platform();
             let arch = tauri_plugin_os::arch();
@@ -219,6 +310,15 @@ pub fn run() {
                 }
             });
 
+            // 启动时检测时钟偏差，偏差过大会导致节点握手失败
+            let clock_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                diagnostics::check_clock(&clock_handle).await;
+            });
+
+            // 节点健康监控，连续失败时自动切换节点
+            failover::start(app.handle());
+
             #[cfg(target_os = "macos")]
             {
                 app.set_activation_policy(tauri::ActivationPolicy::Accessory);
@@ -0,0 +1,243 @@
+use lazy_static::lazy_static;
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_store::StoreExt;
+
+use crate::captive;
+use crate::core::{self, ProxyMode};
+use crate::interfaces::{self, DefaultGateway, NetworkInterface};
+use crate::network_profiles;
+
+// 默认路由变化时对内核的处理：none（默认）/ reload / restart
+const NETWORK_CHANGE_ACTION_STORE_KEY: &str = "network_change_action_key";
+// 默认路由变化时是否检测认证网络，默认开启
+const NETWORK_CHANGE_CAPTIVE_CHECK_STORE_KEY: &str = "network_change_captive_check_key";
+// 收到变更后等待这么久没有新消息再处理，切换网络时内核会连续发出大量消息
+const DEBOUNCE: Duration = Duration::from_millis(1500);
+
+lazy_static! {
+    // 同一时间只处理一次网络变化
+    static ref REACTION: tokio::sync::Mutex<()> = tokio::sync::Mutex::new(());
+}
+
+/// 当前网络状态
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct NetworkState {
+    pub gateway: Option<DefaultGateway>,
+    pub interfaces: Vec<NetworkInterface>,
+}
+
+/// network-changed 事件内容
+#[derive(Clone, Debug, Serialize)]
+pub struct NetworkChange {
+    pub old: NetworkState,
+    pub new: NetworkState,
+    pub default_route_changed: bool,
+}
+
+/// 读取当前网络状态，忽略回环网卡
+pub fn current_state() -> NetworkState {
+    let interfaces = interfaces::list()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|i| !i.is_loopback)
+        .collect();
+    NetworkState {
+        gateway: interfaces::default_gateway(),
+        interfaces,
+    }
+}
+
+fn store_value(app: &AppHandle, key: &str) -> Option<serde_json::Value> {
+    app.store("settings.json")
+        .ok()
+        .and_then(|store| store.get(key))
+}
+
+/// 订阅 rtnetlink 的网卡、地址与路由变更
+fn open_netlink_socket() -> std::io::Result<i32> {
+    let fd = unsafe {
+        libc::socket(
+            libc::AF_NETLINK,
+            libc::SOCK_RAW | libc::SOCK_CLOEXEC,
+            libc::NETLINK_ROUTE,
+        )
+    };
+    if fd < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    let mut addr: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+    addr.nl_family = libc::AF_NETLINK as u16;
+    addr.nl_groups = (libc::RTMGRP_LINK
+        | libc::RTMGRP_IPV4_IFADDR
+        | libc::RTMGRP_IPV6_IFADDR
+        | libc::RTMGRP_IPV4_ROUTE
+        | libc::RTMGRP_IPV6_ROUTE) as u32;
+    let res = unsafe {
+        libc::bind(
+            fd,
+            &addr as *const libc::sockaddr_nl as *const libc::sockaddr,
+            std::mem::size_of::<libc::sockaddr_nl>() as u32,
+        )
+    };
+    if res < 0 {
+        let err = std::io::Error::last_os_error();
+        unsafe { libc::close(fd) };
+        return Err(err);
+    }
+    Ok(fd)
+}
+
+/// 等待 fd 可读，超时返回 false
+fn wait_readable(fd: i32, timeout: Option<Duration>) -> bool {
+    let mut pfd = libc::pollfd {
+        fd,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    let timeout_ms = timeout.map(|t| t.as_millis() as i32).unwrap_or(-1);
+    unsafe { libc::poll(&mut pfd, 1, timeout_ms) > 0 }
+}
+
+/// 读出所有待处理的消息。只需要知道有变化，消息内容直接丢弃，状态统一重新读取
+fn drain(fd: i32, buf: &mut [u8]) -> std::io::Result<()> {
+    loop {
+        let n = unsafe {
+            libc::recv(
+                fd,
+                buf.as_mut_ptr() as *mut libc::c_void,
+                buf.len(),
+                libc::MSG_DONTWAIT,
+            )
+        };
+        if n > 0 {
+            continue;
+        }
+        let err = std::io::Error::last_os_error();
+        return match err.raw_os_error() {
+            // ENOBUFS 表示消息过多被内核丢弃，重新读取状态即可
+            Some(libc::EAGAIN) | Some(libc::ENOBUFS) => Ok(()),
+            _ if n == 0 => Ok(()),
+            _ => Err(err),
+        };
+    }
+}
+
+/// 依次处理网络方案与默认路由变化，避免两者同时停止、启动内核
+pub async fn react_to_change(app: AppHandle, default_route_changed: bool) {
+    let _guard = REACTION.lock().await;
+    // 切换方案时内核已被停止并由前端按方案重新启动，不再重载或重启
+    let activated = network_profiles::evaluate(&app).await;
+    if default_route_changed {
+        react_to_default_route_change(app, !activated).await;
+    }
+}
+
+async fn react_to_default_route_change(app: AppHandle, restart_allowed: bool) {
+    let action = store_value(&app, NETWORK_CHANGE_ACTION_STORE_KEY)
+        .and_then(|v| v.as_str().map(|s| s.to_string()))
+        .unwrap_or_else(|| "none".to_string());
+
+    let session = core::current_session().filter(|_| restart_allowed);
+    if let Some((mode, path)) = session {
+        match action.as_str() {
+            "reload" => match core::reload_config(app.clone(), mode.is_tun()).await {
+                Ok(_) => log::info!("[netmon] Config reloaded after network change"),
+                Err(e) => log::error!("[netmon] Failed to reload config: {}", e),
+            },
+            "restart" => restart_core(&app, mode, path).await,
+            _ => {}
+        }
+    }
+
+    let captive_check = store_value(&app, NETWORK_CHANGE_CAPTIVE_CHECK_STORE_KEY)
+        .and_then(|v| v.as_bool())
+        .unwrap_or(true);
+    if captive_check {
+        let result = captive::detect(&app).await;
+        if let Err(e) = app.emit("captive-portal-status", result) {
+            log::error!("Failed to emit captive-portal-status event: {}", e);
+        }
+    }
+}
+
+async fn restart_core(app: &AppHandle, mode: ProxyMode, path: String) {
+    if let Err(e) = core::stop(app.clone()).await {
+        log::error!("[netmon] Failed to stop core: {}", e);
+        return;
+    }
+    match core::start(app.clone(), path, mode).await {
+        Ok(_) => log::info!("[netmon] Core restarted after network change"),
+        Err(e) => log::error!("[netmon] Failed to restart core: {}", e),
+    }
+}
+
+fn watch(app: AppHandle, fd: i32) {
+    let mut buf = vec![0u8; 16 * 1024];
+    let mut state = current_state();
+    loop {
+        if !wait_readable(fd, None) {
+            continue;
+        }
+        let mut result = drain(fd, &mut buf);
+        while result.is_ok() && wait_readable(fd, Some(DEBOUNCE)) {
+            result = drain(fd, &mut buf);
+        }
+        if let Err(e) = result {
+            log::error!("[netmon] netlink socket error: {}", e);
+            std::thread::sleep(Duration::from_secs(5));
+            continue;
+        }
+
+        let new_state = current_state();
+        if new_state == state {
+            continue;
+        }
+        // ARP 表中网关 MAC 可能稍后才出现，只比较网卡与网关地址
+        let route = |s: &NetworkState| {
+            s.gateway
+                .as_ref()
+                .map(|g| (g.interface.clone(), g.address.clone()))
+        };
+        let default_route_changed = route(&new_state) != route(&state);
+        log::info!(
+            "[netmon] Network changed, gateway {:?} -> {:?}",
+            state.gateway,
+            new_state.gateway
+        );
+        let change = NetworkChange {
+            old: state,
+            new: new_state.clone(),
+            default_route_changed,
+        };
+        if let Err(e) = app.emit("network-changed", change) {
+            log::error!("Failed to emit network-changed event: {}", e);
+        }
+        tauri::async_runtime::spawn(react_to_change(app.clone(), default_route_changed));
+        state = new_state;
+    }
+}
+
+/// 启动网络变化监听
+pub fn start(app: &AppHandle) {
+    let fd = match open_netlink_socket() {
+        Ok(fd) => fd,
+        Err(e) => {
+            log::error!("[netmon] Failed to open netlink socket: {}", e);
+            return;
+        }
+    };
+    let app = app.clone();
+
+    // 启动时按当前网络匹配一次方案，等待前端完成事件监听
+    let profile_app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        react_to_change(profile_app, false).await;
+    });
+
+    std::thread::spawn(move || watch(app, fd));
+    log::info!("[netmon] Network monitor started");
+}
@@ -0,0 +1,345 @@
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::net::Ipv4Addr;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_store::StoreExt;
+
+use crate::core::{self, ProxyMode};
+use crate::interfaces;
+use crate::network_monitor;
+
+const NETWORK_PROFILES_STORE_KEY: &str = "network_profiles_key";
+// 与前端 SSI_STORE_KEY、ENABLE_TUN_STORE_KEY、PROXY_MODE_STORE_KEY 一致
+const SUBSCRIPTION_STORE_KEY: &str = "selected_subscription_identifier";
+const ENABLE_TUN_STORE_KEY: &str = "enable_tun_key";
+const PROXY_MODE_STORE_KEY: &str = "proxy_mode_key";
+
+/// 网络匹配条件，填写的条件需要全部满足
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProfileMatch {
+    pub gateway_mac: Option<String>,
+    pub ssid: Option<String>,
+    /// IPv4 网段，如 192.168.1.0/24
+    pub subnet: Option<String>,
+}
+
+/// 网络配置方案
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NetworkProfile {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "match")]
+    pub matcher: ProfileMatch,
+    /// 订阅标识，为空时沿用当前订阅
+    pub subscription: Option<String>,
+    pub mode: ProxyMode,
+    /// false 表示在该网络下关闭代理
+    pub enabled: bool,
+}
+
+/// 当前网络的特征
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct NetworkIdentity {
+    pub gateway_mac: Option<String>,
+    pub ssid: Option<String>,
+    /// 默认路由网卡上的 IPv4 地址
+    pub addresses: Vec<String>,
+}
+
+/// network-profile-activated 事件内容
+#[derive(Clone, Debug, Serialize)]
+pub struct ProfileActivation {
+    pub profile: NetworkProfile,
+    pub network: NetworkIdentity,
+}
+
+lazy_static! {
+    // 当前生效的方案 id，网络未变化时不重复切换
+    static ref ACTIVE_PROFILE: Mutex<Option<String>> = Mutex::new(None);
+}
+
+/// 通过 NetworkManager 获取当前连接的 Wi-Fi 名称
+fn current_ssid() -> Option<String> {
+    let output = std::process::Command::new("nmcli")
+        .args(["-t", "-f", "active,ssid", "dev", "wifi"])
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.strip_prefix("yes:"))
+        // nmcli -t 会将 SSID 中的冒号转义为 \:
+        .map(|ssid| ssid.replace("\\:", ":"))
+        .filter(|ssid| !ssid.is_empty())
+}
+
+/// 读取当前网络的网关 MAC、SSID 与地址
+pub fn current_network() -> NetworkIdentity {
+    let gateway = interfaces::default_gateway();
+    let addresses = gateway
+        .as_ref()
+        .and_then(|gateway| {
+            interfaces::list()
+                .ok()?
+                .into_iter()
+                .find(|i| i.name == gateway.interface)
+        })
+        .map(|interface| {
+            interface
+                .ipv4
+                .iter()
+                .map(|addr| addr.address.clone())
+                .collect()
+        })
+        .unwrap_or_default();
+    NetworkIdentity {
+        gateway_mac: gateway.and_then(|g| g.mac),
+        ssid: current_ssid(),
+        addresses,
+    }
+}
+
+fn subnet_contains(subnet: &str, address: &str) -> bool {
+    let (network, prefix) = subnet.split_once('/').unwrap_or((subnet, "32"));
+    let (Ok(network), Ok(prefix), Ok(address)) = (
+        network.trim().parse::<Ipv4Addr>(),
+        prefix.trim().parse::<u32>(),
+        address.parse::<Ipv4Addr>(),
+    ) else {
+        return false;
+    };
+    if prefix > 32 {
+        return false;
+    }
+    let mask = if prefix == 0 {
+        0
+    } else {
+        u32::MAX << (32 - prefix)
+    };
+    u32::from(network) & mask == u32::from(address) & mask
+}
+
+fn matches(matcher: &ProfileMatch, network: &NetworkIdentity) -> bool {
+    // 没有任何条件的方案不参与匹配
+    if *matcher == ProfileMatch::default() {
+        return false;
+    }
+    let mac_ok = matcher.gateway_mac.as_ref().is_none_or(|mac| {
+        network
+            .gateway_mac
+            .as_ref()
+            .is_some_and(|current| current.eq_ignore_ascii_case(mac.trim()))
+    });
+    let ssid_ok = matcher
+        .ssid
+        .as_ref()
+        .is_none_or(|ssid| network.ssid.as_deref() == Some(ssid.as_str()));
+    let subnet_ok = matcher.subnet.as_ref().is_none_or(|subnet| {
+        network
+            .addresses
+            .iter()
+            .any(|addr| subnet_contains(subnet, addr))
+    });
+    mac_ok && ssid_ok && subnet_ok
+}
+
+/// 读取所有网络配置方案
+pub fn load(app: &AppHandle) -> Vec<NetworkProfile> {
+    app.store("settings.json")
+        .ok()
+        .and_then(|store| store.get(NETWORK_PROFILES_STORE_KEY))
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
+}
+
+async fn activate(app: &AppHandle, profile: NetworkProfile, network: NetworkIdentity) {
+    log::info!(
+        "[profiles] Network profile {} matched, enabled={}, mode={:?}",
+        profile.name,
+        profile.enabled,
+        profile.mode
+    );
+
+    // 无论开启还是切换，先停止正在运行的内核
+    if core::current_session().is_some() {
+        if let Err(e) = core::stop(app.clone()).await {
+            log::error!("[profiles] Failed to stop core: {}", e);
+            return;
+        }
+    }
+    if !profile.enabled {
+        return;
+    }
+
+    if let Ok(store) = app.store("settings.json") {
+        if let Some(subscription) = &profile.subscription {
+            store.set(SUBSCRIPTION_STORE_KEY, Value::String(subscription.clone()));
+        }
+        store.set(ENABLE_TUN_STORE_KEY, Value::Bool(profile.mode.is_tun()));
+        store.set(PROXY_MODE_STORE_KEY, serde_json::json!(profile.mode));
+        if let Err(e) = store.save() {
+            log::error!("[profiles] Failed to save settings: {}", e);
+        }
+    }
+
+    // 配置文件由前端根据订阅生成，前端收到事件后生成配置并以方案中的模式启动内核
+    if let Err(e) = app.emit(
+        "network-profile-activated",
+        ProfileActivation { profile, network },
+    ) {
+        log::error!("Failed to emit network-profile-activated event: {}", e);
+    }
+}
+
+/// 根据当前网络匹配方案，匹配结果变化时切换，返回是否切换了方案
+pub async fn evaluate(app: &AppHandle) -> bool {
+    let profiles = load(app);
+    if profiles.is_empty() {
+        return false;
+    }
+    let network = current_network();
+    let matched = profiles.into_iter().find(|p| matches(&p.matcher, &network));
+
+    {
+        let mut active = ACTIVE_PROFILE.lock().unwrap_or_else(|e| e.into_inner());
+        let matched_id = matched.as_ref().map(|p| p.id.clone());
+        if *active == matched_id {
+            return false;
+        }
+        *active = matched_id;
+    }
+
+    // 没有匹配的方案时保持当前状态
+    match matched {
+        Some(profile) => {
+            activate(app, profile, network).await;
+            true
+        }
+        None => false,
+    }
+}
+
+/// 获取所有网络配置方案
+#[tauri::command]
+pub fn get_network_profiles(app: AppHandle) -> Vec<NetworkProfile> {
+    load(&app)
+}
+
+/// 保存网络配置方案，并立即按当前网络重新匹配
+#[tauri::command]
+pub async fn set_network_profiles(
+    app: AppHandle,
+    profiles: Vec<NetworkProfile>,
+) -> Result<(), String> {
+    let store = app.store("settings.json").map_err(|e| e.to_string())?;
+    store.set(
+        NETWORK_PROFILES_STORE_KEY,
+        serde_json::to_value(&profiles).map_err(|e| e.to_string())?,
+    );
+    store.save().map_err(|e| e.to_string())?;
+
+    *ACTIVE_PROFILE.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    network_monitor::react_to_change(app, false).await;
+    Ok(())
+}
+
+/// 获取当前网络特征，便于根据当前网络创建方案
+#[tauri::command]
+pub fn get_current_network() -> NetworkIdentity {
+    current_network()
+}
+
+/// 获取当前生效的方案 id
+#[tauri::command]
+pub fn get_active_network_profile() -> Option<String> {
+    ACTIVE_PROFILE
+        .lock()
+        .map(|active| active.clone())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn network(mac: Option<&str>, ssid: Option<&str>, addresses: &[&str]) -> NetworkIdentity {
+        NetworkIdentity {
+            gateway_mac: mac.map(|s| s.to_string()),
+            ssid: ssid.map(|s| s.to_string()),
+            addresses: addresses.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn subnet_contains_respects_prefix() {
+        assert!(subnet_contains("192.168.1.0/24", "192.168.1.42"));
+        assert!(!subnet_contains("192.168.1.0/24", "192.168.2.42"));
+        assert!(subnet_contains(" 10.0.0.0 / 8 ", "10.255.0.1"));
+        // 网段地址带主机位时按掩码比较
+        assert!(subnet_contains("172.16.5.9/16", "172.16.200.1"));
+    }
+
+    #[test]
+    fn subnet_contains_handles_prefix_edges() {
+        assert!(subnet_contains("0.0.0.0/0", "203.0.113.7"));
+        assert!(subnet_contains("203.0.113.7/32", "203.0.113.7"));
+        assert!(!subnet_contains("203.0.113.7/32", "203.0.113.8"));
+        // 不写前缀时按单个地址匹配
+        assert!(subnet_contains("203.0.113.7", "203.0.113.7"));
+        assert!(!subnet_contains("203.0.113.0", "203.0.113.7"));
+        assert!(!subnet_contains("203.0.113.0/33", "203.0.113.7"));
+    }
+
+    #[test]
+    fn subnet_contains_rejects_malformed_input() {
+        assert!(!subnet_contains("", "192.168.1.1"));
+        assert!(!subnet_contains("192.168.1.0/", "192.168.1.1"));
+        assert!(!subnet_contains("192.168.1.0/-1", "192.168.1.1"));
+        assert!(!subnet_contains("192.168.1/24", "192.168.1.1"));
+        assert!(!subnet_contains("192.168.1.0/24", "192.168.1.1/24"));
+        // 只支持 IPv4 网段
+        assert!(!subnet_contains("fd00::/8", "fd00::1"));
+        assert!(!subnet_contains("::/0", "192.168.1.1"));
+        assert!(!subnet_contains("0.0.0.0/0", "::ffff:192.168.1.1"));
+    }
+
+    #[test]
+    fn empty_matcher_never_matches() {
+        let current = network(Some("aa:bb:cc:dd:ee:ff"), Some("Home"), &["192.168.1.2"]);
+        assert!(!matches(&ProfileMatch::default(), &current));
+    }
+
+    #[test]
+    fn matches_requires_all_conditions() {
+        let matcher = ProfileMatch {
+            gateway_mac: Some(" AA:BB:CC:DD:EE:FF ".to_string()),
+            ssid: Some("Home".to_string()),
+            subnet: Some("192.168.1.0/24".to_string()),
+        };
+        let home = network(
+            Some("aa:bb:cc:dd:ee:ff"),
+            Some("Home"),
+            &["fe80::1", "192.168.1.2"],
+        );
+        assert!(matches(&matcher, &home));
+
+        let other_ssid = network(Some("aa:bb:cc:dd:ee:ff"), Some("home"), &["192.168.1.2"]);
+        assert!(!matches(&matcher, &other_ssid));
+        let no_gateway = network(None, Some("Home"), &["192.168.1.2"]);
+        assert!(!matches(&matcher, &no_gateway));
+        let other_subnet = network(Some("aa:bb:cc:dd:ee:ff"), Some("Home"), &["10.0.0.2"]);
+        assert!(!matches(&matcher, &other_subnet));
+    }
+
+    #[test]
+    fn matches_ignores_unset_conditions() {
+        let matcher = ProfileMatch {
+            subnet: Some("10.0.0.0/8".to_string()),
+            ..Default::default()
+        };
+        assert!(matches(&matcher, &network(None, None, &["10.1.2.3"])));
+        assert!(!matches(&matcher, &network(None, None, &[])));
+    }
+}
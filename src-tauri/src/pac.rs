@@ -0,0 +1,518 @@
+use lazy_static::lazy_static;
+use serde::Serialize;
+use serde_json::Value;
+use std::net::Ipv4Addr;
+use std::sync::{Arc, Mutex, RwLock};
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+// 混合入站地址，PAC 中需要代理的流量都会发往这里
+const MIXED_INBOUND: &str = "127.0.0.1:6789";
+const PAC_PATH: &str = "/proxy.pac";
+
+// 只有这些字段的规则才能在 PAC 中等价表达，其余条件（端口、协议等）会与域名条件组成“且”关系
+const PAC_RULE_KEYS: [&str; 8] = [
+    "domain",
+    "domain_suffix",
+    "domain_keyword",
+    "ip_cidr",
+    "ip_is_private",
+    "rule_set",
+    "outbound",
+    "action",
+];
+// 规则集中可以在 PAC 中表达的规则字段
+const PAC_RULE_SET_KEYS: [&str; 4] = ["domain", "domain_suffix", "domain_keyword", "ip_cidr"];
+
+/// PAC 匹配规则，依次匹配，命中即返回
+#[derive(Clone, Debug, PartialEq, Serialize)]
+struct PacRule {
+    kind: &'static str,
+    value: String,
+    mask: String,
+    direct: bool,
+}
+
+impl PacRule {
+    fn new(kind: &'static str, value: &str, direct: bool) -> Self {
+        Self {
+            kind,
+            value: value.to_string(),
+            mask: String::new(),
+            direct,
+        }
+    }
+}
+
+struct PacServer {
+    handle: JoinHandle<()>,
+    url: String,
+    content: Arc<RwLock<String>>,
+}
+
+lazy_static! {
+    static ref PAC_SERVER: Mutex<Option<PacServer>> = Mutex::new(None);
+}
+
+fn string_list(rule: &Value, key: &str) -> Vec<String> {
+    rule.get(key)
+        .and_then(|v| v.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| item.as_str())
+                .map(|s| s.to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn cidr_rule(cidr: &str, direct: bool) -> Option<PacRule> {
+    // PAC 的 isInNet 只支持 IPv4
+    let (ip, prefix) = cidr.split_once('/').unwrap_or((cidr, "32"));
+    let ip: Ipv4Addr = ip.trim().parse().ok()?;
+    let prefix: u32 = prefix.trim().parse().ok().filter(|p| *p <= 32)?;
+    let mask = if prefix == 0 {
+        0
+    } else {
+        u32::MAX << (32 - prefix)
+    };
+    Some(PacRule {
+        kind: "cidr",
+        value: Ipv4Addr::from(u32::from(ip) & mask).to_string(),
+        mask: Ipv4Addr::from(mask).to_string(),
+        direct,
+    })
+}
+
+fn push_address_rules(rules: &mut Vec<PacRule>, rule: &Value, direct: bool) {
+    for domain in string_list(rule, "domain") {
+        rules.push(PacRule::new("domain", &domain, direct));
+    }
+    for suffix in string_list(rule, "domain_suffix") {
+        rules.push(PacRule::new(
+            "suffix",
+            suffix.trim_start_matches('.'),
+            direct,
+        ));
+    }
+    for keyword in string_list(rule, "domain_keyword") {
+        rules.push(PacRule::new("keyword", &keyword, direct));
+    }
+    for cidr in string_list(rule, "ip_cidr") {
+        if let Some(r) = cidr_rule(&cidr, direct) {
+            rules.push(r);
+        }
+    }
+}
+
+/// 读取规则集中的规则，只支持内联规则集与本地源文件格式，二进制与远程规则集无法读取
+fn rule_set_rules(config: &Value, tag: &str) -> Option<Vec<Value>> {
+    let set = config["route"]["rule_set"]
+        .as_array()?
+        .iter()
+        .find(|set| set["tag"] == tag)?;
+    let source = match set["type"].as_str() {
+        Some("inline") => set.clone(),
+        Some("local") => {
+            let path = set["path"].as_str()?;
+            let format = set["format"]
+                .as_str()
+                .unwrap_or(if path.ends_with(".json") {
+                    "source"
+                } else {
+                    "binary"
+                });
+            if format != "source" {
+                return None;
+            }
+            let content = std::fs::read_to_string(path).ok()?;
+            serde_json::from_str(&content).ok()?
+        }
+        _ => return None,
+    };
+    source["rules"].as_array().cloned()
+}
+
+/// 从 sing-box 配置中提取可以在 PAC 中表达的路由规则
+fn rules_from_config(config: &Value) -> (Vec<PacRule>, bool) {
+    let direct_tags: Vec<&str> = config["outbounds"]
+        .as_array()
+        .map(|outbounds| {
+            outbounds
+                .iter()
+                .filter(|o| o["type"] == "direct")
+                .filter_map(|o| o["tag"].as_str())
+                .collect()
+        })
+        .unwrap_or_default();
+    let is_direct = |tag: &str| direct_tags.contains(&tag);
+
+    let mut rules = Vec::new();
+    for rule in config["route"]["rules"].as_array().into_iter().flatten() {
+        let Some(fields) = rule.as_object() else {
+            continue;
+        };
+        if fields.keys().any(|k| !PAC_RULE_KEYS.contains(&k.as_str())) {
+            continue;
+        }
+        if rule["action"].as_str().is_some_and(|a| a != "route") {
+            continue;
+        }
+        let Some(outbound) = rule["outbound"].as_str() else {
+            continue;
+        };
+        let direct = is_direct(outbound);
+        if rule["ip_is_private"].as_bool() == Some(true) {
+            for cidr in [
+                "10.0.0.0/8",
+                "172.16.0.0/12",
+                "192.168.0.0/16",
+                "127.0.0.0/8",
+            ] {
+                rules.extend(cidr_rule(cidr, direct));
+            }
+        }
+        push_address_rules(&mut rules, rule, direct);
+        for tag in string_list(rule, "rule_set") {
+            let Some(set_rules) = rule_set_rules(config, &tag) else {
+                log::info!(
+                    "[pac] Skipping rule set {}: only inline and local source rule sets can be used in PAC",
+                    tag
+                );
+                continue;
+            };
+            for set_rule in set_rules.iter().filter(|r| {
+                r.as_object()
+                    .is_some_and(|f| f.keys().all(|k| PAC_RULE_SET_KEYS.contains(&k.as_str())))
+            }) {
+                push_address_rules(&mut rules, set_rule, direct);
+            }
+        }
+    }
+
+    let final_direct = config["route"]["final"].as_str().is_some_and(is_direct);
+    (rules, final_direct)
+}
+
+/// 读取用户自定义的直连/代理规则（与前端 setCustomRuleSet 的格式一致）
+fn custom_rules(app: &AppHandle) -> Vec<PacRule> {
+    let Ok(store) = app.store("settings.json") else {
+        return Vec::new();
+    };
+    let mut rules = Vec::new();
+    for (key, direct) in [
+        ("custom_ruleset_direct", true),
+        ("custom_ruleset_proxy", false),
+    ] {
+        let parsed = store
+            .get(key)
+            .and_then(|v| v.as_str().map(|s| s.to_string()))
+            .and_then(|s| serde_json::from_str::<Value>(&s).ok());
+        if let Some(config) = parsed {
+            push_address_rules(&mut rules, &config, direct);
+        }
+    }
+    rules
+}
+
+/// 生成 PAC 文件内容
+fn render(rules: &[PacRule], final_direct: bool) -> String {
+    let proxy = format!("PROXY {0}; SOCKS5 {0}", MIXED_INBOUND);
+    let rules_json = serde_json::to_string(rules).unwrap_or_else(|_| "[]".to_string());
+    format!(
+        r#"// Generated by OneBox, do not edit.
+var PROXY = "{proxy}";
+var FINAL = {final_action};
+var RULES = {rules_json};
+
+function FindProxyForURL(url, host) {{
+    if (isPlainHostName(host) || host === "localhost") {{
+        return "DIRECT";
+    }}
+    var isIPv4 = /^\d+\.\d+\.\d+\.\d+$/.test(host);
+    for (var i = 0; i < RULES.length; i++) {{
+        var rule = RULES[i];
+        var hit = false;
+        if (rule.kind === "domain") {{
+            hit = host === rule.value;
+        }} else if (rule.kind === "suffix") {{
+            hit = host === rule.value || dnsDomainIs(host, "." + rule.value);
+        }} else if (rule.kind === "keyword") {{
+            hit = host.indexOf(rule.value) >= 0;
+        }} else if (rule.kind === "cidr") {{
+            hit = isIPv4 && isInNet(host, rule.value, rule.mask);
+        }}
+        if (hit) {{
+            return rule.direct ? "DIRECT" : PROXY;
+        }}
+    }}
+    return FINAL;
+}}
+"#,
+        proxy = proxy,
+        final_action = if final_direct { "\"DIRECT\"" } else { "PROXY" },
+        rules_json = rules_json,
+    )
+}
+
+/// 根据当前配置文件和自定义规则生成 PAC
+pub fn generate(app: &AppHandle, config_path: &str) -> anyhow::Result<String> {
+    let content = std::fs::read_to_string(config_path)?;
+    let config: Value = serde_json::from_str(&content)?;
+    let (config_rules, final_direct) = rules_from_config(&config);
+
+    // 自定义规则优先
+    let mut rules = custom_rules(app);
+    for rule in config_rules {
+        if !rules.contains(&rule) {
+            rules.push(rule);
+        }
+    }
+    log::info!("Generated PAC with {} rules", rules.len());
+    Ok(render(&rules, final_direct))
+}
+
+async fn serve(listener: TcpListener, content: Arc<RwLock<String>>) {
+    loop {
+        let (mut stream, _) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                log::error!("PAC server accept error: {}", e);
+                continue;
+            }
+        };
+        let content = content.clone();
+        tokio::spawn(async move {
+            // 只需要读出请求头，任何路径都返回同一个 PAC
+            let mut buf = [0u8; 1024];
+            let _ = stream.read(&mut buf).await;
+            let body = content.read().map(|c| c.clone()).unwrap_or_default();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/x-ns-proxy-autoconfig\r\nContent-Length: {}\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            if let Err(e) = stream.write_all(response.as_bytes()).await {
+                log::error!("PAC server write error: {}", e);
+            }
+        });
+    }
+}
+
+/// 启动本地 PAC 服务，返回 PAC 地址
+pub async fn start(app: &AppHandle, config_path: &str) -> anyhow::Result<String> {
+    let pac = generate(app, config_path)?;
+
+    // 已在运行时只更新内容
+    if let Some(server) = PAC_SERVER
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+    {
+        if let Ok(mut content) = server.content.write() {
+            *content = pac;
+        }
+        return Ok(server.url.clone());
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let url = format!("http://{}{}", listener.local_addr()?, PAC_PATH);
+    let content = Arc::new(RwLock::new(pac));
+    let handle = tokio::spawn(serve(listener, content.clone()));
+    log::info!("PAC server listening on {}", url);
+
+    let mut server = PAC_SERVER.lock().unwrap_or_else(|e| e.into_inner());
+    *server = Some(PacServer {
+        handle,
+        url: url.clone(),
+        content,
+    });
+    Ok(url)
+}
+
+/// 重新生成 PAC 内容（配置重载后调用）
+pub fn refresh(app: &AppHandle, config_path: &str) -> anyhow::Result<()> {
+    let pac = generate(app, config_path)?;
+    let server = PAC_SERVER.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(server) = server.as_ref() {
+        if let Ok(mut content) = server.content.write() {
+            *content = pac;
+        }
+    }
+    Ok(())
+}
+
+/// 是否为 OneBox 本地 PAC 服务的地址，端口每次启动随机分配
+pub fn is_local_url(url: &str) -> bool {
+    url.strip_prefix("http://127.0.0.1:")
+        .and_then(|rest| rest.strip_suffix(PAC_PATH))
+        .is_some_and(|port| port.parse::<u16>().is_ok())
+}
+
+/// 停止本地 PAC 服务
+pub fn stop() {
+    let mut server = PAC_SERVER.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(server) = server.take() {
+        server.handle.abort();
+        log::info!("PAC server stopped");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rule(kind: &'static str, value: &str, direct: bool) -> PacRule {
+        PacRule::new(kind, value, direct)
+    }
+
+    fn cidr(value: &str, mask: &str, direct: bool) -> PacRule {
+        PacRule {
+            kind: "cidr",
+            value: value.to_string(),
+            mask: mask.to_string(),
+            direct,
+        }
+    }
+
+    #[test]
+    fn converts_ipv4_cidrs() {
+        let cases = [
+            ("10.1.2.3/8", Some(cidr("10.0.0.0", "255.0.0.0", true))),
+            (
+                "192.168.1.77/24",
+                Some(cidr("192.168.1.0", "255.255.255.0", true)),
+            ),
+            ("1.1.1.1", Some(cidr("1.1.1.1", "255.255.255.255", true))),
+            ("1.1.1.1/32", Some(cidr("1.1.1.1", "255.255.255.255", true))),
+            ("8.8.8.8/0", Some(cidr("0.0.0.0", "0.0.0.0", true))),
+            (
+                " 172.16.0.0 / 12 ",
+                Some(cidr("172.16.0.0", "255.240.0.0", true)),
+            ),
+            ("1.1.1.1/33", None),
+            ("fd00::/8", None),
+            ("not-an-ip/8", None),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(cidr_rule(input, true), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn extracts_rules_expressible_in_pac() {
+        let config = json!({
+            "outbounds": [
+                {"type": "selector", "tag": "ExitGateway"},
+                {"type": "direct", "tag": "DIRECT"},
+            ],
+            "route": {
+                "final": "ExitGateway",
+                "rules": [
+                    {"action": "sniff"},
+                    {"domain": ["example.com"], "domain_suffix": [".cn"], "outbound": "DIRECT"},
+                    {"domain_keyword": ["google"], "ip_cidr": ["8.8.8.0/24"], "outbound": "ExitGateway"},
+                    // 带端口条件的规则无法在 PAC 中表达
+                    {"domain": ["skipped.com"], "port": [443], "outbound": "DIRECT"},
+                    {"ip_is_private": true, "action": "route", "outbound": "DIRECT"},
+                    {"domain": ["blocked.com"], "action": "reject"},
+                ],
+            },
+        });
+        let (rules, final_direct) = rules_from_config(&config);
+        assert!(!final_direct);
+        assert_eq!(
+            rules,
+            vec![
+                rule("domain", "example.com", true),
+                rule("suffix", "cn", true),
+                rule("keyword", "google", false),
+                cidr("8.8.8.0", "255.255.255.0", false),
+                cidr("10.0.0.0", "255.0.0.0", true),
+                cidr("172.16.0.0", "255.240.0.0", true),
+                cidr("192.168.0.0", "255.255.0.0", true),
+                cidr("127.0.0.0", "255.0.0.0", true),
+            ]
+        );
+    }
+
+    #[test]
+    fn expands_inline_and_local_rule_sets() {
+        let mut file = tempfile::Builder::new().suffix(".json").tempfile().unwrap();
+        std::io::Write::write_all(
+            &mut file,
+            br#"{"version": 2, "rules": [{"ip_cidr": ["1.0.1.0/24"]}]}"#,
+        )
+        .unwrap();
+        let path = file.path().to_string_lossy().to_string();
+        let config = json!({
+            "outbounds": [{"type": "direct", "tag": "DIRECT"}],
+            "route": {
+                "rule_set": [
+                    {"type": "inline", "tag": "cn-inline", "rules": [
+                        {"domain_suffix": ["cn"]},
+                        // 正则无法在 PAC 中表达
+                        {"domain_regex": ["^ad\\."]},
+                    ]},
+                    {"type": "local", "tag": "cn-local", "path": path},
+                    {"type": "local", "tag": "cn-binary", "format": "binary", "path": path},
+                    {"type": "remote", "tag": "geosite-cn", "url": "https://example.com/geosite-cn.srs"},
+                ],
+                "rules": [
+                    {"rule_set": ["geosite-cn", "cn-inline", "missing"], "outbound": "DIRECT"},
+                    {"rule_set": ["cn-local", "cn-binary"], "outbound": "DIRECT"},
+                ],
+            },
+        });
+        let (rules, _) = rules_from_config(&config);
+        assert_eq!(
+            rules,
+            vec![
+                rule("suffix", "cn", true),
+                cidr("1.0.1.0", "255.255.255.0", true),
+            ]
+        );
+    }
+
+    #[test]
+    fn recognises_local_pac_urls() {
+        assert!(is_local_url("http://127.0.0.1:41234/proxy.pac"));
+        assert!(!is_local_url("http://127.0.0.1/proxy.pac"));
+        assert!(!is_local_url("http://127.0.0.1:99999/proxy.pac"));
+        assert!(!is_local_url("http://127.0.0.1:8080/other.pac"));
+        assert!(!is_local_url("http://wpad.example.com:8080/proxy.pac"));
+        assert!(!is_local_url(""));
+    }
+
+    #[test]
+    fn final_outbound_decides_fallback() {
+        let config = |final_tag: &str| {
+            json!({
+                "outbounds": [{"type": "direct", "tag": "direct"}, {"type": "selector", "tag": "ExitGateway"}],
+                "route": {"final": final_tag, "rules": []},
+            })
+        };
+        let (rules, final_direct) = rules_from_config(&config("direct"));
+        assert!(rules.is_empty());
+        assert!(final_direct);
+        assert!(render(&rules, final_direct).contains("var FINAL = \"DIRECT\";"));
+
+        let (rules, final_direct) = rules_from_config(&config("ExitGateway"));
+        assert!(!final_direct);
+        assert!(render(&rules, final_direct).contains("var FINAL = PROXY;"));
+    }
+
+    #[test]
+    fn renders_rules_and_proxy_address() {
+        let pac = render(&[rule("suffix", "cn", true)], false);
+        assert!(pac.contains("var PROXY = \"PROXY 127.0.0.1:6789; SOCKS5 127.0.0.1:6789\";"));
+        assert!(pac
+            .contains(r#"var RULES = [{"kind":"suffix","value":"cn","mask":"","direct":true}];"#));
+        assert!(pac.contains("function FindProxyForURL(url, host)"));
+    }
+}
@@ -0,0 +1,245 @@
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+
+use crate::clash_api::{ClashApi, Proxy};
+use crate::database;
+
+// 内核启动后等待 Clash API 可用的最长时间
+const READY_TIMEOUT: Duration = Duration::from_secs(15);
+const READY_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 恢复的选择，也是 selectors-restored 事件内容中的一项
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SelectorChoice {
+    pub selector: String,
+    pub node: String,
+}
+
+/// 保存选择器的选择，同一订阅下每个选择器只保留最后一次
+pub async fn remember(
+    pool: &SqlitePool,
+    identifier: &str,
+    selector: &str,
+    node: &str,
+) -> Result<(), sqlx::Error> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default();
+    sqlx::query(
+        "INSERT INTO selector_choices (identifier, selector, node, updated_at) VALUES (?, ?, ?, ?)
+         ON CONFLICT (identifier, selector) DO UPDATE SET node = excluded.node, updated_at = excluded.updated_at",
+    )
+    .bind(identifier)
+    .bind(selector)
+    .bind(node)
+    .bind(now)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// 订阅下保存的所有选择
+pub async fn saved(
+    pool: &SqlitePool,
+    identifier: &str,
+) -> Result<Vec<SelectorChoice>, sqlx::Error> {
+    sqlx::query(
+        "SELECT selector, node FROM selector_choices WHERE identifier = ? ORDER BY selector",
+    )
+    .bind(identifier)
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|row| {
+        Ok(SelectorChoice {
+            selector: row.try_get("selector")?,
+            node: row.try_get("node")?,
+        })
+    })
+    .collect()
+}
+
+/// 需要重新应用的选择：跳过已不存在的选择器或节点，以及已经选中的节点
+fn plan_restore(
+    saved: Vec<SelectorChoice>,
+    proxies: &HashMap<String, Proxy>,
+) -> Vec<SelectorChoice> {
+    saved
+        .into_iter()
+        .filter(|choice| match proxies.get(&choice.selector) {
+            Some(selector) if selector.is_selector() => {
+                let exists = selector
+                    .all
+                    .as_ref()
+                    .is_some_and(|all| all.contains(&choice.node));
+                if !exists {
+                    log::info!(
+                        "[selection] Node {} no longer exists in {}, skipping",
+                        choice.node,
+                        choice.selector
+                    );
+                }
+                exists && selector.now.as_deref() != Some(choice.node.as_str())
+            }
+            _ => {
+                log::info!(
+                    "[selection] Selector {} no longer exists, skipping",
+                    choice.selector
+                );
+                false
+            }
+        })
+        .collect()
+}
+
+/// 记录通过应用切换的节点
+pub async fn record(app: &AppHandle, selector: &str, node: &str) {
+    let identifier = database::selected_subscription(app).unwrap_or_default();
+    let result = match database::pool(app).await {
+        Ok(pool) => remember(pool, &identifier, selector, node)
+            .await
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    if let Err(e) = result {
+        log::error!(
+            "[selection] Failed to record {} -> {}: {}",
+            selector,
+            node,
+            e
+        );
+    }
+}
+
+async fn wait_ready(api: &ClashApi) -> bool {
+    let started = std::time::Instant::now();
+    while started.elapsed() < READY_TIMEOUT {
+        if api.version().await.is_ok() {
+            return true;
+        }
+        tokio::time::sleep(READY_POLL_INTERVAL).await;
+    }
+    false
+}
+
+async fn apply(app: &AppHandle) -> Result<Vec<SelectorChoice>, String> {
+    let identifier = database::selected_subscription(app).unwrap_or_default();
+    let pool = database::pool(app).await.map_err(|e| e.to_string())?;
+    let saved = saved(pool, &identifier).await.map_err(|e| e.to_string())?;
+    if saved.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let api = ClashApi::current(app);
+    if !wait_ready(&api).await {
+        return Err("Clash API is not ready".to_string());
+    }
+    let proxies = api.proxies().await.map_err(|e| e.to_string())?;
+    let mut restored = Vec::new();
+    for choice in plan_restore(saved, &proxies) {
+        match api.select(&choice.selector, &choice.node).await {
+            Ok(()) => {
+                log::info!(
+                    "[selection] Restored {} -> {}",
+                    choice.selector,
+                    choice.node
+                );
+                restored.push(choice);
+            }
+            Err(e) => log::error!(
+                "[selection] Failed to restore {} -> {}: {}",
+                choice.selector,
+                choice.node,
+                e
+            ),
+        }
+    }
+    Ok(restored)
+}
+
+/// 内核启动后重新应用当前订阅保存的选择
+pub async fn restore(app: AppHandle) {
+    match apply(&app).await {
+        Ok(restored) if !restored.is_empty() => {
+            if let Err(e) = app.emit("selectors-restored", restored) {
+                log::error!("Failed to emit selectors-restored event: {}", e);
+            }
+        }
+        Ok(_) => {}
+        Err(e) => log::error!("[selection] Failed to restore selectors: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    fn choice(selector: &str, node: &str) -> SelectorChoice {
+        SelectorChoice {
+            selector: selector.to_string(),
+            node: node.to_string(),
+        }
+    }
+
+    fn selector(proxy_type: &str, now: &str, all: &[&str]) -> Proxy {
+        Proxy {
+            proxy_type: proxy_type.to_string(),
+            now: Some(now.to_string()),
+            all: Some(all.iter().map(|s| s.to_string()).collect()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn skips_missing_and_current_nodes() {
+        let proxies = HashMap::from([
+            (
+                "ExitGateway".to_string(),
+                selector("Selector", "HK", &["HK", "JP"]),
+            ),
+            (
+                "Streaming".to_string(),
+                selector("Selector", "JP", &["HK", "JP"]),
+            ),
+            ("Auto".to_string(), selector("URLTest", "HK", &["HK", "JP"])),
+        ]);
+        let saved = vec![
+            choice("ExitGateway", "JP"),
+            choice("Streaming", "JP"),
+            choice("Auto", "JP"),
+            choice("Gone", "JP"),
+            choice("ExitGateway", "US"),
+        ];
+        assert_eq!(
+            plan_restore(saved, &proxies),
+            vec![choice("ExitGateway", "JP")]
+        );
+    }
+
+    #[tokio::test]
+    async fn keeps_last_choice_per_subscription() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        database::init_schema(&pool).await.unwrap();
+        remember(&pool, "a", "ExitGateway", "HK").await.unwrap();
+        remember(&pool, "a", "ExitGateway", "JP").await.unwrap();
+        remember(&pool, "b", "ExitGateway", "US").await.unwrap();
+
+        assert_eq!(
+            saved(&pool, "a").await.unwrap(),
+            vec![choice("ExitGateway", "JP")]
+        );
+        assert_eq!(
+            saved(&pool, "b").await.unwrap(),
+            vec![choice("ExitGateway", "US")]
+        );
+    }
+}
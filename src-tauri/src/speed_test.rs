@@ -0,0 +1,586 @@
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_http::reqwest;
+use tauri_plugin_store::StoreExt;
+use tokio::sync::watch;
+
+use crate::clash_api::ClashApi;
+use crate::core;
+
+const SPEED_TEST_OPTIONS_STORE_KEY: &str = "speed_test_options_key";
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 测速参数
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpeedTestOptions {
+    pub download_url: String,
+    /// 为空时不测上传
+    pub upload_url: Option<String>,
+    /// 上传总量（字节）
+    pub upload_bytes: u64,
+    /// 每次上传请求的大小（字节）
+    pub upload_chunk_bytes: u64,
+    /// 每个方向的最长测试时间（秒）
+    pub max_secs: u64,
+    /// 指定节点时临时切换选择器，测试完成后恢复
+    pub node: Option<String>,
+    pub inbound: String,
+}
+
+impl Default for SpeedTestOptions {
+    fn default() -> Self {
+        Self {
+            download_url: "https://speed.cloudflare.com/__down?bytes=50000000".to_string(),
+            upload_url: None,
+            upload_bytes: 10 * 1024 * 1024,
+            upload_chunk_bytes: 1024 * 1024,
+            max_secs: 15,
+            node: None,
+            inbound: "127.0.0.1:6789".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Download,
+    Upload,
+}
+
+/// speed-test-progress 事件内容
+#[derive(Clone, Debug, Serialize)]
+pub struct SpeedTestProgress {
+    pub node: Option<String>,
+    pub direction: Direction,
+    pub bytes: u64,
+    pub elapsed_ms: u64,
+    pub instant_mbps: f64,
+    pub average_mbps: f64,
+}
+
+/// 单个方向的测速结果
+#[derive(Clone, Debug, Serialize)]
+pub struct TransferResult {
+    pub bytes: u64,
+    pub duration_ms: u64,
+    pub average_mbps: f64,
+    pub peak_mbps: f64,
+}
+
+/// 测速汇总，可按节点保存
+#[derive(Clone, Debug, Serialize)]
+pub struct SpeedTestSummary {
+    /// 实际测试的节点
+    pub node: Option<String>,
+    pub download: Option<TransferResult>,
+    pub upload: Option<TransferResult>,
+    pub cancelled: bool,
+    pub error: Option<String>,
+    /// 测试开始时间（Unix 秒）
+    pub timestamp: u64,
+}
+
+lazy_static! {
+    // 正在进行的测速，发送 true 表示取消
+    static ref SPEED_TEST: Mutex<Option<watch::Sender<bool>>> = Mutex::new(None);
+}
+
+fn mbps(bytes: u64, elapsed: Duration) -> f64 {
+    let secs = elapsed.as_secs_f64();
+    if secs <= 0.0 {
+        return 0.0;
+    }
+    bytes as f64 * 8.0 / secs / 1_000_000.0
+}
+
+/// 进度回调，应用中转发为 speed-test-progress 事件
+type ProgressSink<'a> = &'a (dyn Fn(SpeedTestProgress) + Sync);
+
+/// 统计传输量并按固定间隔发送进度
+struct Meter<'a> {
+    sink: ProgressSink<'a>,
+    node: Option<String>,
+    direction: Direction,
+    started: Instant,
+    bytes: u64,
+    last_emit: Instant,
+    last_bytes: u64,
+    peak_mbps: f64,
+}
+
+impl<'a> Meter<'a> {
+    fn new(sink: ProgressSink<'a>, node: Option<String>, direction: Direction) -> Self {
+        let now = Instant::now();
+        Self {
+            sink,
+            node,
+            direction,
+            started: now,
+            bytes: 0,
+            last_emit: now,
+            last_bytes: 0,
+            peak_mbps: 0.0,
+        }
+    }
+
+    fn add(&mut self, bytes: u64) {
+        self.bytes += bytes;
+        if self.last_emit.elapsed() >= PROGRESS_INTERVAL {
+            self.emit();
+        }
+    }
+
+    fn emit(&mut self) {
+        let instant_mbps = mbps(self.bytes - self.last_bytes, self.last_emit.elapsed());
+        self.peak_mbps = self.peak_mbps.max(instant_mbps);
+        let progress = SpeedTestProgress {
+            node: self.node.clone(),
+            direction: self.direction,
+            bytes: self.bytes,
+            elapsed_ms: self.started.elapsed().as_millis() as u64,
+            instant_mbps,
+            average_mbps: mbps(self.bytes, self.started.elapsed()),
+        };
+        (self.sink)(progress);
+        self.last_emit = Instant::now();
+        self.last_bytes = self.bytes;
+    }
+
+    fn finish(mut self) -> TransferResult {
+        self.emit();
+        let elapsed = self.started.elapsed();
+        TransferResult {
+            bytes: self.bytes,
+            duration_ms: elapsed.as_millis() as u64,
+            average_mbps: mbps(self.bytes, elapsed),
+            peak_mbps: self.peak_mbps,
+        }
+    }
+}
+
+/// 失败时同时返回已完成部分的结果，错误为空表示被取消
+type TransferOutcome = Result<TransferResult, (TransferResult, Option<String>)>;
+
+async fn download(
+    client: &reqwest::Client,
+    options: &SpeedTestOptions,
+    meter: Meter<'_>,
+    cancel: &mut watch::Receiver<bool>,
+) -> TransferOutcome {
+    let mut meter = meter;
+    let deadline = tokio::time::sleep(Duration::from_secs(options.max_secs));
+    tokio::pin!(deadline);
+
+    let mut response = tokio::select! {
+        _ = cancel.changed() => return Err((meter.finish(), None)),
+        _ = &mut deadline => {
+            let error = format!("no response within {} s", options.max_secs);
+            return Err((meter.finish(), Some(error)));
+        }
+        response = client.get(&options.download_url).send() => match response {
+            Ok(response) if response.status().is_success() => response,
+            Ok(response) => {
+                let error = format!("download returned {}", response.status());
+                return Err((meter.finish(), Some(error)));
+            }
+            Err(e) => return Err((meter.finish(), Some(format!("download failed: {}", e)))),
+        },
+    };
+    loop {
+        tokio::select! {
+            _ = cancel.changed() => return Err((meter.finish(), None)),
+            // 达到最长时间即结束，按已下载的数据计算
+            _ = &mut deadline => break,
+            chunk = response.chunk() => match chunk {
+                Ok(Some(chunk)) => meter.add(chunk.len() as u64),
+                Ok(None) => break,
+                Err(e) => return Err((meter.finish(), Some(format!("download interrupted: {}", e)))),
+            },
+        }
+    }
+    Ok(meter.finish())
+}
+
+/// 每次上传请求的大小，至少 1 字节且不超过上传总量
+fn upload_chunk_len(options: &SpeedTestOptions) -> u64 {
+    options
+        .upload_chunk_bytes
+        .clamp(1, options.upload_bytes.max(1))
+}
+
+/// 分多次 POST 上传，每完成一次更新进度
+async fn upload(
+    client: &reqwest::Client,
+    url: &str,
+    options: &SpeedTestOptions,
+    meter: Meter<'_>,
+    cancel: &mut watch::Receiver<bool>,
+) -> TransferOutcome {
+    let mut meter = meter;
+    let chunk_len = upload_chunk_len(options);
+    let payload = vec![0u8; chunk_len as usize];
+    let deadline = tokio::time::sleep(Duration::from_secs(options.max_secs));
+    tokio::pin!(deadline);
+
+    while meter.bytes < options.upload_bytes {
+        tokio::select! {
+            _ = cancel.changed() => return Err((meter.finish(), None)),
+            _ = &mut deadline => break,
+            response = client.post(url).body(payload.clone()).send() => match response {
+                Ok(response) if response.status().is_success() => meter.add(chunk_len),
+                Ok(response) => {
+                    let error = format!("upload returned {}", response.status());
+                    return Err((meter.finish(), Some(error)));
+                }
+                Err(e) => return Err((meter.finish(), Some(format!("upload failed: {}", e)))),
+            },
+        }
+    }
+    Ok(meter.finish())
+}
+
+/// 切换选择器，返回原来选中的节点
+async fn switch_selector(api: &ClashApi, group: &str, node: &str) -> Result<String, String> {
+    let selector = api
+        .proxy(group)
+        .await
+        .map_err(|e| format!("query selector {}: {}", group, e))?;
+    let previous = selector
+        .now
+        .clone()
+        .filter(|_| selector.is_selector())
+        .ok_or_else(|| format!("{} is not a selector", group))?;
+    if !selector.all.unwrap_or_default().iter().any(|n| n == node) {
+        return Err(format!("node {} is not in selector {}", node, group));
+    }
+    api.select(group, node)
+        .await
+        .map_err(|e| format!("switch {} to {}: {}", group, node, e))?;
+    Ok(previous)
+}
+
+/// 记录取消或错误，返回已完成部分的结果
+fn settle(outcome: TransferOutcome, summary: &mut SpeedTestSummary) -> TransferResult {
+    match outcome {
+        Ok(result) => result,
+        Err((result, error)) => {
+            summary.cancelled = error.is_none();
+            summary.error = error;
+            result
+        }
+    }
+}
+
+/// 指定节点时先切换选择器，测试结束后恢复原来的节点
+async fn measure(
+    api: &ClashApi,
+    group: &str,
+    client: &reqwest::Client,
+    options: &SpeedTestOptions,
+    sink: ProgressSink<'_>,
+    cancel: &mut watch::Receiver<bool>,
+) -> Result<SpeedTestSummary, String> {
+    let mut restore = None;
+    if let Some(node) = &options.node {
+        let previous = switch_selector(api, group, node).await?;
+        if previous != *node {
+            restore = Some(previous);
+        }
+    }
+
+    let mut summary = SpeedTestSummary {
+        node: options.node.clone(),
+        download: None,
+        upload: None,
+        cancelled: false,
+        error: None,
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default(),
+    };
+    let meter = Meter::new(sink, options.node.clone(), Direction::Download);
+    let outcome = download(client, options, meter, cancel).await;
+    summary.download = Some(settle(outcome, &mut summary));
+    if let Some(url) = &options.upload_url {
+        if !summary.cancelled && summary.error.is_none() {
+            let meter = Meter::new(sink, options.node.clone(), Direction::Upload);
+            let outcome = upload(client, url, options, meter, cancel).await;
+            summary.upload = Some(settle(outcome, &mut summary));
+        }
+    }
+
+    if let Some(previous) = restore {
+        if let Err(e) = switch_selector(api, group, &previous).await {
+            log::error!("[speedtest] Failed to restore selector {}: {}", group, e);
+        }
+    }
+    Ok(summary)
+}
+
+async fn run(
+    app: &AppHandle,
+    options: &SpeedTestOptions,
+    cancel: &mut watch::Receiver<bool>,
+) -> Result<SpeedTestSummary, String> {
+    let (_, config_path) = core::current_session().ok_or("Proxy is not running")?;
+    let proxy =
+        reqwest::Proxy::all(format!("http://{}", options.inbound)).map_err(|e| e.to_string())?;
+    let client = reqwest::ClientBuilder::new()
+        .proxy(proxy)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let api = ClashApi::current(app);
+    let group = core::exit_selector(&config_path);
+    let sink = |progress: SpeedTestProgress| {
+        if let Err(e) = app.emit("speed-test-progress", progress) {
+            log::error!("Failed to emit speed-test-progress event: {}", e);
+        }
+    };
+    measure(&api, &group, &client, options, &sink, cancel).await
+}
+
+/// 读取测速参数
+pub fn options(app: &AppHandle) -> SpeedTestOptions {
+    app.store("settings.json")
+        .ok()
+        .and_then(|store| store.get(SPEED_TEST_OPTIONS_STORE_KEY))
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
+}
+
+/// 通过代理测速，测试过程中发送 speed-test-progress 事件
+#[tauri::command]
+pub async fn run_speed_test(
+    app: AppHandle,
+    options: Option<SpeedTestOptions>,
+) -> Result<SpeedTestSummary, String> {
+    let options = options.unwrap_or_else(|| self::options(&app));
+    let mut cancel = {
+        let mut running = SPEED_TEST.lock().unwrap_or_else(|e| e.into_inner());
+        if running.is_some() {
+            return Err("A speed test is already running".to_string());
+        }
+        let (tx, rx) = watch::channel(false);
+        *running = Some(tx);
+        rx
+    };
+
+    let summary = run(&app, &options, &mut cancel).await;
+    SPEED_TEST.lock().unwrap_or_else(|e| e.into_inner()).take();
+    let summary = summary?;
+    log::info!(
+        "[speedtest] node={:?} download={:?} upload={:?} cancelled={} error={:?}",
+        summary.node,
+        summary.download.as_ref().map(|r| r.average_mbps),
+        summary.upload.as_ref().map(|r| r.average_mbps),
+        summary.cancelled,
+        summary.error
+    );
+    Ok(summary)
+}
+
+/// 取消正在进行的测速
+#[tauri::command]
+pub fn cancel_speed_test() -> bool {
+    match SPEED_TEST
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+    {
+        Some(tx) => tx.send(true).is_ok(),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{HttpServer, Response};
+
+    const SELECTOR: &str =
+        r#"{"name":"ExitGateway","type":"Selector","now":"HK","all":["HK","JP"]}"#;
+
+    /// 同时模拟 Clash API 与下载地址
+    async fn stand_in(chunks: &'static [&'static str]) -> HttpServer {
+        HttpServer::start(move |line| async move {
+            if line.starts_with("GET /proxies/ExitGateway ") {
+                Response::json("200 OK", SELECTOR)
+            } else if line.starts_with("GET /proxies/direct ") {
+                Response::json("200 OK", r#"{"name":"direct","type":"Direct"}"#)
+            } else if line.starts_with("PUT /proxies/") {
+                Response::json("204 No Content", "")
+            } else if line.starts_with("GET /download ") {
+                Response::stream(chunks)
+            } else {
+                Response::json("404 Not Found", r#"{"message":"not found"}"#)
+            }
+        })
+        .await
+    }
+
+    fn options(server: &HttpServer, node: Option<&str>) -> SpeedTestOptions {
+        SpeedTestOptions {
+            download_url: format!("http://{}/download", server.address),
+            max_secs: 1,
+            node: node.map(|s| s.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn converts_bytes_to_megabits() {
+        assert_eq!(mbps(1_000_000, Duration::from_secs(1)), 8.0);
+        assert_eq!(mbps(125_000, Duration::from_millis(500)), 2.0);
+        assert_eq!(mbps(1_000_000, Duration::ZERO), 0.0);
+        assert_eq!(mbps(0, Duration::from_secs(3)), 0.0);
+    }
+
+    #[test]
+    fn clamps_upload_chunks() {
+        let chunk = |upload_chunk_bytes, upload_bytes| {
+            upload_chunk_len(&SpeedTestOptions {
+                upload_chunk_bytes,
+                upload_bytes,
+                ..Default::default()
+            })
+        };
+        assert_eq!(chunk(1024 * 1024, 10 * 1024 * 1024), 1024 * 1024);
+        assert_eq!(chunk(0, 10 * 1024 * 1024), 1);
+        assert_eq!(chunk(4096, 1000), 1000);
+        assert_eq!(chunk(4096, 0), 1);
+    }
+
+    #[test]
+    fn meter_tracks_peak_and_average() {
+        let reports = Mutex::new(Vec::new());
+        let sink = |progress: SpeedTestProgress| reports.lock().unwrap().push(progress);
+        let mut meter = Meter::new(&sink, Some("HK".to_string()), Direction::Download);
+        let ago = |secs| {
+            Instant::now()
+                .checked_sub(Duration::from_secs(secs))
+                .unwrap()
+        };
+        meter.started = ago(2);
+
+        // 间隔未到时只累计，不发送进度
+        meter.add(100);
+        assert!(reports.lock().unwrap().is_empty());
+
+        meter.last_emit = ago(1);
+        meter.add(999_900);
+        meter.last_emit = ago(1);
+        meter.add(250_000);
+        let result = meter.finish();
+
+        let reports = reports.lock().unwrap();
+        assert_eq!(reports.len(), 3);
+        assert!(reports[0].instant_mbps > 7.5 && reports[0].instant_mbps <= 8.0);
+        assert!(reports[1].instant_mbps > 1.8 && reports[1].instant_mbps <= 2.0);
+        assert_eq!(reports[2].bytes, 1_250_000);
+        assert_eq!(reports[2].node.as_deref(), Some("HK"));
+        assert_eq!(result.bytes, 1_250_000);
+        assert_eq!(result.peak_mbps, reports[0].instant_mbps);
+        assert!(result.average_mbps > 4.5 && result.average_mbps <= 5.0);
+    }
+
+    #[tokio::test]
+    async fn switch_selector_checks_group_and_node() {
+        let server = stand_in(&[]).await;
+        let api = server.clash_api("");
+        assert_eq!(
+            switch_selector(&api, "ExitGateway", "JP").await.unwrap(),
+            "HK"
+        );
+        assert!(switch_selector(&api, "ExitGateway", "US")
+            .await
+            .unwrap_err()
+            .contains("not in selector"));
+        assert!(switch_selector(&api, "direct", "JP")
+            .await
+            .unwrap_err()
+            .contains("not a selector"));
+        let puts: Vec<String> = server
+            .requests()
+            .into_iter()
+            .filter(|r| r.starts_with("PUT"))
+            .collect();
+        assert_eq!(puts.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn downloads_on_node_and_restores_selector() {
+        let server = stand_in(&["aaaa", "bbbb", "cccc"]).await;
+        let client = reqwest::Client::new();
+        let (_tx, mut cancel) = watch::channel(false);
+        let summary = measure(
+            &server.clash_api(""),
+            "ExitGateway",
+            &client,
+            &options(&server, Some("JP")),
+            &|_| {},
+            &mut cancel,
+        )
+        .await
+        .unwrap();
+
+        assert!(!summary.cancelled);
+        assert_eq!(summary.error, None);
+        assert_eq!(summary.download.unwrap().bytes, 12);
+        assert!(summary.upload.is_none());
+        let requests: Vec<String> = server
+            .requests()
+            .iter()
+            .map(|r| r.split(" HTTP").next().unwrap().to_string())
+            .collect();
+        assert_eq!(
+            requests,
+            [
+                "GET /proxies/ExitGateway",
+                "PUT /proxies/ExitGateway",
+                "GET /download",
+                "GET /proxies/ExitGateway",
+                "PUT /proxies/ExitGateway",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn cancels_download_and_still_restores_selector() {
+        let server = stand_in(&["a"; 100]).await;
+        let client = reqwest::Client::new();
+        let (tx, mut cancel) = watch::channel(false);
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            tx.send(true).unwrap();
+        });
+        let started = Instant::now();
+        let summary = measure(
+            &server.clash_api(""),
+            "ExitGateway",
+            &client,
+            &options(&server, Some("JP")),
+            &|_| {},
+            &mut cancel,
+        )
+        .await
+        .unwrap();
+
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert!(summary.cancelled);
+        assert_eq!(summary.error, None);
+        let bytes = summary.download.unwrap().bytes;
+        assert!(bytes > 0 && bytes < 100);
+        assert!(server
+            .requests()
+            .last()
+            .unwrap()
+            .starts_with("PUT /proxies/ExitGateway"));
+    }
+}
@@ -0,0 +1,120 @@
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+use crate::clash_api::{ClashApi, Endpoint};
+
+/// 模拟服务返回的响应
+pub struct Response {
+    status: String,
+    chunks: Vec<String>,
+    /// 分块传输，每块之间稍作停顿
+    streaming: bool,
+}
+
+impl Response {
+    pub fn json(status: &str, body: &str) -> Self {
+        Self {
+            status: status.to_string(),
+            chunks: vec![body.to_string()],
+            streaming: false,
+        }
+    }
+
+    /// 模拟 /traffic 等持续推送的接口
+    pub fn stream(chunks: &[&str]) -> Self {
+        Self {
+            status: "200 OK".to_string(),
+            chunks: chunks.iter().map(|s| s.to_string()).collect(),
+            streaming: true,
+        }
+    }
+
+    async fn write(&self, stream: &mut tokio::net::TcpStream) -> std::io::Result<()> {
+        if !self.streaming {
+            let body = self.chunks.concat();
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                self.status,
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).await?;
+            return stream.shutdown().await;
+        }
+        let head = format!(
+            "HTTP/1.1 {}\r\nContent-Type: application/json\r\nTransfer-Encoding: chunked\r\n\r\n",
+            self.status
+        );
+        stream.write_all(head.as_bytes()).await?;
+        for chunk in &self.chunks {
+            let chunk = format!("{:x}\r\n{}\r\n", chunk.len(), chunk);
+            stream.write_all(chunk.as_bytes()).await?;
+            stream.flush().await?;
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        // 流式接口不会主动结束，保持连接直到客户端断开
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        Ok(())
+    }
+}
+
+/// 本地 HTTP/1.1 服务，按请求行返回响应，并记录收到的请求行
+pub struct HttpServer {
+    pub address: String,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl HttpServer {
+    pub async fn start<F, Fut>(handler: F) -> Self
+    where
+        F: Fn(String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Response> + Send,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = requests.clone();
+        let handler = Arc::new(handler);
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let (seen, handler) = (seen.clone(), handler.clone());
+                tokio::spawn(async move {
+                    let mut buf = vec![0u8; 4096];
+                    let Ok(n) = stream.read(&mut buf).await else {
+                        return;
+                    };
+                    let request = String::from_utf8_lossy(&buf[..n]).to_string();
+                    let line = request.lines().next().unwrap_or_default().to_string();
+                    seen.lock().unwrap().push(line.clone());
+                    let _ = handler(line).await.write(&mut stream).await;
+                });
+            }
+        });
+        Self { address, requests }
+    }
+
+    /// 对所有请求返回同一个响应
+    pub async fn fixed(status: &str, body: &str) -> Self {
+        let (status, body) = (status.to_string(), body.to_string());
+        Self::start(move |_| {
+            let response = Response::json(&status, &body);
+            async move { response }
+        })
+        .await
+    }
+
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// 指向该服务的 Clash API 客户端
+    pub fn clash_api(&self, secret: &str) -> ClashApi {
+        ClashApi::new(Endpoint {
+            address: self.address.clone(),
+            secret: secret.to_string(),
+        })
+    }
+}
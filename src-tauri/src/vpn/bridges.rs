@@ -0,0 +1,259 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fs;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+use crate::vpn::helper;
+
+// 是否自动排除容器与虚拟机网桥，默认开启
+const EXCLUDE_BRIDGES_STORE_KEY: &str = "tun_exclude_bridges_key";
+// 按网卡名称覆盖默认行为：true 排除出 TUN，false 仍走 TUN
+const BRIDGE_OVERRIDES_STORE_KEY: &str = "tun_bridge_overrides_key";
+
+/// 本地网桥或虚拟网卡
+#[derive(Clone, Debug, Serialize)]
+pub struct BridgeInterface {
+    pub name: String,
+    /// docker / libvirt / podman / other
+    pub kind: String,
+    pub subnets: Vec<String>,
+    /// 启动 TUN 时是否加入 route_exclude_address
+    pub excluded: bool,
+}
+
+fn bridge_kind(name: &str) -> Option<&'static str> {
+    if name == "docker0" || name.starts_with("br-") {
+        Some("docker")
+    } else if name.starts_with("virbr") {
+        Some("libvirt")
+    } else if name.starts_with("podman") || name.starts_with("cni-podman") {
+        Some("podman")
+    } else {
+        None
+    }
+}
+
+/// 将地址与前缀长度转换为网段，如 172.17.0.1/16 -> 172.17.0.0/16
+fn network_cidr(local: &str, prefix: u32) -> Option<String> {
+    if let Ok(ip) = local.parse::<Ipv4Addr>() {
+        let prefix = prefix.min(32);
+        let mask = if prefix == 0 {
+            0
+        } else {
+            u32::MAX << (32 - prefix)
+        };
+        return Some(format!(
+            "{}/{}",
+            Ipv4Addr::from(u32::from(ip) & mask),
+            prefix
+        ));
+    }
+    let ip = local.parse::<Ipv6Addr>().ok()?;
+    let prefix = prefix.min(128);
+    let mask = if prefix == 0 {
+        0
+    } else {
+        u128::MAX << (128 - prefix)
+    };
+    Some(format!(
+        "{}/{}",
+        Ipv6Addr::from(u128::from(ip) & mask),
+        prefix
+    ))
+}
+
+fn overrides(app: &AppHandle) -> Map<String, Value> {
+    app.store("settings.json")
+        .ok()
+        .and_then(|store| store.get(BRIDGE_OVERRIDES_STORE_KEY))
+        .and_then(|value| value.as_object().cloned())
+        .unwrap_or_default()
+}
+
+fn exclude_bridges_enabled(app: &AppHandle) -> bool {
+    app.store("settings.json")
+        .ok()
+        .and_then(|store| store.get(EXCLUDE_BRIDGES_STORE_KEY))
+        .and_then(|value| value.as_bool())
+        .unwrap_or(true)
+}
+
+/// 枚举本机网卡，返回网桥/虚拟网卡及用户手动加入的网卡
+pub fn list(app: &AppHandle) -> Result<Vec<BridgeInterface>, String> {
+    let enabled = exclude_bridges_enabled(app);
+    let overrides = overrides(app);
+    let links = helper::ip_json(&["addr", "show"])?;
+
+    let interfaces = links
+        .as_array()
+        .map(|links| links.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|link| {
+            let name = link["ifname"].as_str()?;
+            let kind = bridge_kind(name);
+            let manual = overrides.get(name).and_then(|v| v.as_bool());
+            if kind.is_none() && manual.is_none() {
+                return None;
+            }
+            let subnets: Vec<String> = link["addr_info"]
+                .as_array()
+                .map(|infos| {
+                    infos
+                        .iter()
+                        .filter(|info| info["scope"] != "link")
+                        .filter_map(|info| {
+                            network_cidr(
+                                info["local"].as_str()?,
+                                info["prefixlen"].as_u64()? as u32,
+                            )
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            Some(BridgeInterface {
+                name: name.to_string(),
+                kind: kind.unwrap_or("other").to_string(),
+                subnets,
+                excluded: manual.unwrap_or(enabled && kind.is_some()),
+            })
+        })
+        .collect();
+    Ok(interfaces)
+}
+
+/// 将需要排除的网桥网段写入配置文件中 tun 入站的 route_exclude_address
+pub fn inject_route_exclude(app: &AppHandle, config_path: &str) -> anyhow::Result<()> {
+    let subnets: Vec<String> = list(app)
+        .map_err(|e| anyhow::anyhow!(e))?
+        .into_iter()
+        .filter(|iface| iface.excluded)
+        .flat_map(|iface| iface.subnets)
+        .collect();
+    if subnets.is_empty() {
+        return Ok(());
+    }
+
+    let content = fs::read_to_string(config_path)?;
+    let mut config: Value = serde_json::from_str(&content)?;
+    let Some(tun) = config["inbounds"]
+        .as_array_mut()
+        .and_then(|inbounds| inbounds.iter_mut().find(|i| i["type"] == "tun"))
+    else {
+        return Ok(());
+    };
+
+    if !tun["route_exclude_address"].is_array() {
+        tun["route_exclude_address"] = Value::Array(Vec::new());
+    }
+    let Some(exclude) = tun["route_exclude_address"].as_array_mut() else {
+        return Ok(());
+    };
+    let mut added = Vec::new();
+    for subnet in subnets {
+        let value = Value::String(subnet.clone());
+        if !exclude.contains(&value) {
+            exclude.push(value);
+            added.push(subnet);
+        }
+    }
+    if added.is_empty() {
+        return Ok(());
+    }
+
+    fs::write(config_path, serde_json::to_string_pretty(&config)?)?;
+    log::info!("Excluded bridge subnets from TUN: {}", added.join(", "));
+    Ok(())
+}
+
+/// 获取本机网桥/虚拟网卡及其是否排除出 TUN
+#[tauri::command]
+pub fn get_bridge_interfaces(app: AppHandle) -> Result<Vec<BridgeInterface>, String> {
+    list(&app)
+}
+
+/// 设置网卡是否排除出 TUN，传入 null 恢复默认行为
+#[tauri::command]
+pub fn set_bridge_interface_excluded(
+    app: AppHandle,
+    name: String,
+    excluded: Option<bool>,
+) -> Result<(), String> {
+    let store = app.store("settings.json").map_err(|e| e.to_string())?;
+    let mut overrides = overrides(&app);
+    match excluded {
+        Some(excluded) => {
+            overrides.insert(name, Value::Bool(excluded));
+        }
+        None => {
+            overrides.remove(&name);
+        }
+    }
+    store.set(BRIDGE_OVERRIDES_STORE_KEY, Value::Object(overrides));
+    store.save().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_bridge_names() {
+        assert_eq!(bridge_kind("docker0"), Some("docker"));
+        assert_eq!(bridge_kind("br-3f2a9c1d0e4b"), Some("docker"));
+        assert_eq!(bridge_kind("virbr0"), Some("libvirt"));
+        assert_eq!(bridge_kind("podman0"), Some("podman"));
+        assert_eq!(bridge_kind("cni-podman0"), Some("podman"));
+        assert_eq!(bridge_kind("docker1"), None);
+        assert_eq!(bridge_kind("eth0"), None);
+        assert_eq!(bridge_kind("br0"), None);
+        assert_eq!(bridge_kind(""), None);
+    }
+
+    #[test]
+    fn masks_ipv4_host_bits() {
+        assert_eq!(
+            network_cidr("172.17.0.1", 16).as_deref(),
+            Some("172.17.0.0/16")
+        );
+        assert_eq!(
+            network_cidr("192.168.122.1", 24).as_deref(),
+            Some("192.168.122.0/24")
+        );
+        assert_eq!(network_cidr("10.1.2.3", 0).as_deref(), Some("0.0.0.0/0"));
+        assert_eq!(network_cidr("10.1.2.3", 32).as_deref(), Some("10.1.2.3/32"));
+        // 超出范围的前缀按 /32 处理
+        assert_eq!(network_cidr("10.1.2.3", 64).as_deref(), Some("10.1.2.3/32"));
+    }
+
+    #[test]
+    fn masks_ipv6_host_bits() {
+        assert_eq!(
+            network_cidr("fd00:dead:beef::1", 64).as_deref(),
+            Some("fd00:dead:beef::/64")
+        );
+        assert_eq!(
+            network_cidr("fe80::42:acff:fe11:2", 10).as_deref(),
+            Some("fe80::/10")
+        );
+        assert_eq!(network_cidr("2001:db8::1", 0).as_deref(), Some("::/0"));
+        assert_eq!(
+            network_cidr("2001:db8::1", 128).as_deref(),
+            Some("2001:db8::1/128")
+        );
+        assert_eq!(
+            network_cidr("2001:db8::1", 200).as_deref(),
+            Some("2001:db8::1/128")
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_addresses() {
+        assert_eq!(network_cidr("", 24), None);
+        assert_eq!(network_cidr("172.17.0", 16), None);
+        assert_eq!(network_cidr("172.17.0.1/16", 16), None);
+        assert_eq!(network_cidr("fe80::1%docker0", 64), None);
+    }
+}
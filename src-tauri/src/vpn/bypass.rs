@@ -0,0 +1,401 @@
+use std::net::{IpAddr, Ipv4Addr};
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+const BYPASS_STORE_KEY: &str = "system_proxy_bypass_key";
+
+// Windows 的通配符只能按整段匹配，单个 CIDR 展开超过该数量时放宽为上一级通配符
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+const WINDOWS_MAX_WILDCARDS: u32 = 64;
+
+// 默认绕过列表（规范格式）
+pub const DEFAULT_BYPASS: [&str; 9] = [
+    "localhost",
+    "127.0.0.0/8",
+    "::1",
+    "10.0.0.0/8",
+    "172.16.0.0/12",
+    "192.168.0.0/16",
+    "*.local",
+    "*.crashlytics.com",
+    "<local>",
+];
+
+/// 规范格式的绕过条目
+#[derive(Clone, Debug, PartialEq)]
+pub enum BypassEntry {
+    /// 完整域名，如 example.com
+    Domain(String),
+    /// 通配符，如 *.example.com、192.168.*
+    Wildcard(String),
+    /// 单个 IP 地址
+    Ip(IpAddr),
+    /// 网段
+    Cidr(IpAddr, u8),
+    /// 不含点的本地主机名
+    Local,
+}
+
+fn is_valid_label(label: &str) -> bool {
+    !label.is_empty()
+        && label.len() <= 63
+        && !label.starts_with('-')
+        && !label.ends_with('-')
+        && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+impl BypassEntry {
+    /// 解析并校验一个条目
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let entry = input.trim().to_ascii_lowercase();
+        if entry.is_empty() {
+            return Err("empty entry".to_string());
+        }
+        if entry == "<local>" {
+            return Ok(Self::Local);
+        }
+
+        if let Some((ip, prefix)) = entry.split_once('/') {
+            let ip: IpAddr = ip
+                .parse()
+                .map_err(|_| format!("invalid CIDR address: {}", input))?;
+            let max = if ip.is_ipv4() { 32 } else { 128 };
+            let prefix: u8 = prefix
+                .parse()
+                .ok()
+                .filter(|p| *p <= max)
+                .ok_or_else(|| format!("invalid CIDR prefix: {}", input))?;
+            return Ok(Self::Cidr(ip, prefix));
+        }
+
+        if let Ok(ip) = entry.parse::<IpAddr>() {
+            return Ok(Self::Ip(ip));
+        }
+
+        if entry.len() > 253 {
+            return Err(format!("domain too long: {}", input));
+        }
+        if entry.contains('*') {
+            let valid = entry.split('.').all(|l| l == "*" || is_valid_label(l));
+            return if valid {
+                Ok(Self::Wildcard(entry))
+            } else {
+                Err(format!("invalid wildcard: {}", input))
+            };
+        }
+        if entry.split('.').all(is_valid_label) {
+            Ok(Self::Domain(entry))
+        } else {
+            Err(format!("invalid domain: {}", input))
+        }
+    }
+}
+
+/// 解析整个列表，返回所有无效条目的错误信息
+pub fn parse_list(entries: &[String]) -> Result<Vec<BypassEntry>, Vec<String>> {
+    let mut parsed = Vec::new();
+    let mut errors = Vec::new();
+    for entry in entries {
+        match BypassEntry::parse(entry) {
+            Ok(e) => parsed.push(e),
+            Err(e) => errors.push(e),
+        }
+    }
+    if errors.is_empty() {
+        Ok(parsed)
+    } else {
+        Err(errors)
+    }
+}
+
+fn cidr_string(ip: &IpAddr, prefix: u8) -> String {
+    format!("{}/{}", ip, prefix)
+}
+
+// 各平台的转换函数都保留在所有平台上编译，便于统一测试
+/// GNOME/KDE 的 ignore-hosts，逗号分隔，支持 CIDR
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub fn to_linux(entries: &[BypassEntry]) -> String {
+    entries
+        .iter()
+        .filter_map(|entry| match entry {
+            BypassEntry::Domain(d) | BypassEntry::Wildcard(d) => Some(d.clone()),
+            BypassEntry::Ip(ip) => Some(ip.to_string()),
+            BypassEntry::Cidr(ip, prefix) => Some(cidr_string(ip, *prefix)),
+            // Linux 没有 <local> 的概念
+            BypassEntry::Local => None,
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// macOS 的 ExceptionsList，逗号分隔，支持 CIDR 和 <local>
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+pub fn to_macos(entries: &[BypassEntry]) -> String {
+    entries
+        .iter()
+        .map(|entry| match entry {
+            BypassEntry::Domain(d) | BypassEntry::Wildcard(d) => d.clone(),
+            BypassEntry::Ip(ip) => ip.to_string(),
+            BypassEntry::Cidr(ip, prefix) => cidr_string(ip, *prefix),
+            BypassEntry::Local => "<local>".to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// 将 IPv4 网段转换为 Windows 通配符
+///
+/// 网段边界不在整段上时展开为多个通配符；展开数量过多时放宽为上一级通配符（范围会变大）。
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn ipv4_cidr_to_wildcards(ip: Ipv4Addr, prefix: u8) -> Vec<String> {
+    let prefix = prefix as u32;
+    if prefix == 0 {
+        return vec!["*".to_string()];
+    }
+    let mask = u32::MAX << (32 - prefix);
+    let network = u32::from(ip) & mask;
+
+    let mut octets = prefix.div_ceil(8);
+    let mut count = 1u32 << (octets * 8 - prefix);
+    if count > WINDOWS_MAX_WILDCARDS {
+        octets = prefix / 8;
+        count = 1;
+        log::warn!(
+            "{}/{} cannot be expressed exactly with Windows wildcards, widened",
+            ip,
+            prefix
+        );
+        if octets == 0 {
+            return vec!["*".to_string()];
+        }
+    }
+
+    let step = if octets == 4 {
+        1
+    } else {
+        1u32 << (32 - octets * 8)
+    };
+    (0..count)
+        .map(|i| {
+            let value = (network & (u32::MAX << (32 - octets * 8))) + i * step;
+            let parts = Ipv4Addr::from(value).octets();
+            let mut pattern = parts[..octets as usize]
+                .iter()
+                .map(|o| o.to_string())
+                .collect::<Vec<_>>()
+                .join(".");
+            if octets < 4 {
+                pattern.push_str(".*");
+            }
+            pattern
+        })
+        .collect()
+}
+
+/// Windows 的 ProxyOverride，分号分隔，只支持通配符
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub fn to_windows(entries: &[BypassEntry]) -> String {
+    let mut items = Vec::new();
+    for entry in entries {
+        match entry {
+            BypassEntry::Domain(d) | BypassEntry::Wildcard(d) => items.push(d.clone()),
+            BypassEntry::Ip(IpAddr::V4(ip)) => items.push(ip.to_string()),
+            BypassEntry::Ip(IpAddr::V6(ip)) => items.push(format!("[{}]", ip)),
+            BypassEntry::Cidr(IpAddr::V4(ip), prefix) => {
+                items.extend(ipv4_cidr_to_wildcards(*ip, *prefix))
+            }
+            BypassEntry::Cidr(IpAddr::V6(ip), 128) => items.push(format!("[{}]", ip)),
+            BypassEntry::Cidr(IpAddr::V6(ip), prefix) => {
+                log::warn!(
+                    "Skip {}/{}: IPv6 ranges are not supported on Windows",
+                    ip,
+                    prefix
+                );
+            }
+            BypassEntry::Local => items.push("<local>".to_string()),
+        }
+    }
+    items.dedup();
+    items.join(";")
+}
+
+/// 读取用户设置的绕过列表，未设置时返回默认列表
+pub fn load(app: &AppHandle) -> Vec<String> {
+    let stored = app.store("settings.json").ok().and_then(|store| {
+        store.get(BYPASS_STORE_KEY).and_then(|value| {
+            value.as_array().map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| item.as_str().map(|s| s.to_string()))
+                    .collect::<Vec<_>>()
+            })
+        })
+    });
+    stored.unwrap_or_else(|| DEFAULT_BYPASS.iter().map(|s| s.to_string()).collect())
+}
+
+/// 读取绕过列表并转换为当前平台的格式，无效条目会被忽略
+pub fn platform_bypass(app: &AppHandle) -> String {
+    let entries: Vec<BypassEntry> = load(app)
+        .iter()
+        .filter_map(|entry| match BypassEntry::parse(entry) {
+            Ok(e) => Some(e),
+            Err(e) => {
+                log::warn!("Ignore bypass entry: {}", e);
+                None
+            }
+        })
+        .collect();
+
+    #[cfg(target_os = "linux")]
+    {
+        to_linux(&entries)
+    }
+    #[cfg(target_os = "macos")]
+    {
+        to_macos(&entries)
+    }
+    #[cfg(target_os = "windows")]
+    {
+        to_windows(&entries)
+    }
+}
+
+/// 获取系统代理绕过列表
+#[tauri::command]
+pub fn get_proxy_bypass(app: AppHandle) -> Vec<String> {
+    load(&app)
+}
+
+/// 保存系统代理绕过列表，下次设置系统代理时生效
+#[tauri::command]
+pub fn set_proxy_bypass(app: AppHandle, entries: Vec<String>) -> Result<(), String> {
+    parse_list(&entries).map_err(|errors| errors.join("; "))?;
+    let entries: Vec<String> = entries
+        .iter()
+        .map(|e| e.trim().to_ascii_lowercase())
+        .collect();
+    let store = app.store("settings.json").map_err(|e| e.to_string())?;
+    store.set(BYPASS_STORE_KEY, serde_json::json!(entries));
+    store.save().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(entries: &[&str]) -> Vec<BypassEntry> {
+        let entries: Vec<String> = entries.iter().map(|s| s.to_string()).collect();
+        parse_list(&entries).unwrap()
+    }
+
+    #[test]
+    fn parse_accepts_canonical_entries() {
+        assert_eq!(
+            BypassEntry::parse("Example.COM").unwrap(),
+            BypassEntry::Domain("example.com".to_string())
+        );
+        assert_eq!(
+            BypassEntry::parse("*.local").unwrap(),
+            BypassEntry::Wildcard("*.local".to_string())
+        );
+        assert_eq!(
+            BypassEntry::parse("10.0.0.0/8").unwrap(),
+            BypassEntry::Cidr("10.0.0.0".parse().unwrap(), 8)
+        );
+        assert_eq!(
+            BypassEntry::parse("::1").unwrap(),
+            BypassEntry::Ip("::1".parse().unwrap())
+        );
+        assert_eq!(BypassEntry::parse("<local>").unwrap(), BypassEntry::Local);
+    }
+
+    #[test]
+    fn parse_rejects_invalid_entries() {
+        for entry in [
+            "",
+            "10.0.0.0/33",
+            "fd00::/129",
+            "bad_host",
+            "-a.com",
+            "a..b",
+            "*x.com",
+        ] {
+            assert!(BypassEntry::parse(entry).is_err(), "{}", entry);
+        }
+        let entries = vec!["ok.com".to_string(), "1.2.3.4/40".to_string()];
+        assert_eq!(parse_list(&entries).unwrap_err().len(), 1);
+    }
+
+    #[test]
+    fn linux_keeps_cidr_and_drops_local() {
+        let entries = parse(&["localhost", "192.168.0.0/16", "::1", "*.local", "<local>"]);
+        assert_eq!(to_linux(&entries), "localhost,192.168.0.0/16,::1,*.local");
+    }
+
+    #[test]
+    fn macos_keeps_local() {
+        let entries = parse(&["localhost", "10.0.0.0/8", "*.local", "<local>"]);
+        assert_eq!(to_macos(&entries), "localhost,10.0.0.0/8,*.local,<local>");
+    }
+
+    #[test]
+    fn windows_expands_octet_aligned_cidr() {
+        let entries = parse(&[
+            "127.0.0.0/8",
+            "192.168.0.0/16",
+            "10.1.2.0/24",
+            "10.9.9.9/32",
+        ]);
+        assert_eq!(to_windows(&entries), "127.*;192.168.*;10.1.2.*;10.9.9.9");
+    }
+
+    #[test]
+    fn windows_expands_unaligned_cidr_exactly() {
+        let wildcards = ipv4_cidr_to_wildcards("172.16.0.0".parse().unwrap(), 12);
+        assert_eq!(wildcards.len(), 16);
+        assert_eq!(wildcards.first().unwrap(), "172.16.*");
+        assert_eq!(wildcards.last().unwrap(), "172.31.*");
+
+        // 主机位不为零时按网络地址展开
+        let wildcards = ipv4_cidr_to_wildcards("192.168.5.77".parse().unwrap(), 23);
+        assert_eq!(wildcards, vec!["192.168.4.*", "192.168.5.*"]);
+    }
+
+    #[test]
+    fn windows_widens_cidr_that_cannot_be_expressed() {
+        // /9 需要 128 个通配符，放宽为 10.*
+        assert_eq!(
+            ipv4_cidr_to_wildcards("10.0.0.0".parse().unwrap(), 9),
+            vec!["10.*"]
+        );
+        // /25 需要 128 个地址，放宽为 /24
+        assert_eq!(
+            ipv4_cidr_to_wildcards("192.168.1.128".parse().unwrap(), 25),
+            vec!["192.168.1.*"]
+        );
+        // /26 可以精确展开为 64 个地址
+        assert_eq!(
+            ipv4_cidr_to_wildcards("192.168.1.64".parse().unwrap(), 26).len(),
+            64
+        );
+        assert_eq!(
+            ipv4_cidr_to_wildcards("0.0.0.0".parse().unwrap(), 0),
+            vec!["*"]
+        );
+    }
+
+    #[test]
+    fn windows_handles_ipv6() {
+        let entries = parse(&["::1", "::1/128", "fd00::/8", "<local>"]);
+        assert_eq!(to_windows(&entries), "[::1];<local>");
+    }
+
+    #[test]
+    fn default_list_is_valid() {
+        let entries: Vec<String> = DEFAULT_BYPASS.iter().map(|s| s.to_string()).collect();
+        assert!(parse_list(&entries).is_ok());
+    }
+}
@@ -0,0 +1,262 @@
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::net::Ipv4Addr;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_store::StoreExt;
+
+use crate::privilege;
+use crate::vpn::helper;
+
+const GATEWAY_STATE_FILE_NAME: &str = "gateway_state.json";
+const NFT_TABLE: &str = "onebox_gateway";
+const IPV4_FORWARD: &str = "net.ipv4.ip_forward";
+const IPV6_FORWARD: &str = "net.ipv6.conf.all.forwarding";
+
+/// 开启网关模式前的系统状态，停止时据此恢复
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct GatewayState {
+    ipv4_forward: String,
+    ipv6_forward: String,
+    interface: String,
+    address: String,
+    subnet: String,
+}
+
+/// 网关模式状态
+#[derive(Clone, Debug, Serialize)]
+pub struct GatewayStatus {
+    pub enabled: bool,
+    pub ipv4_forwarding: bool,
+    pub ipv6_forwarding: bool,
+    /// 局域网接口名称
+    pub interface: String,
+    /// 局域网设备应填写的网关地址
+    pub gateway_address: String,
+    pub subnet: String,
+}
+
+lazy_static! {
+    // 启用时记录状态文件路径，停止时 stop_tun_process 无法拿到 AppHandle
+    static ref STATE_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+}
+
+fn state_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let config_dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(config_dir.join(GATEWAY_STATE_FILE_NAME))
+}
+
+fn read_sysctl(key: &str) -> String {
+    let path = format!("/proc/sys/{}", key.replace('.', "/"));
+    fs::read_to_string(path)
+        .map(|v| v.trim().to_string())
+        .unwrap_or_else(|_| "0".to_string())
+}
+
+/// 默认路由所在的局域网接口及其 IPv4 地址、网段
+fn lan_interface() -> Result<(String, String, String), String> {
+    let routes = helper::ip_json(&["-4", "route", "show", "default"])?;
+    let interface = routes
+        .as_array()
+        .and_then(|routes| routes.iter().find_map(|r| r["dev"].as_str()))
+        .ok_or("No default route found")?
+        .to_string();
+
+    let addrs = helper::ip_json(&["-4", "addr", "show", "dev", &interface])?;
+    let addr_info = addrs
+        .as_array()
+        .and_then(|links| links.first())
+        .and_then(|link| link["addr_info"].as_array())
+        .and_then(|infos| infos.iter().find(|i| i["family"] == "inet"))
+        .ok_or_else(|| format!("No IPv4 address on {}", interface))?;
+
+    let address = addr_info["local"].as_str().unwrap_or_default().to_string();
+    let prefix = addr_info["prefixlen"].as_u64().unwrap_or(24).min(32) as u32;
+    let ip: Ipv4Addr = address.parse().map_err(|_| "Invalid LAN address")?;
+    let mask = if prefix == 0 {
+        0
+    } else {
+        u32::MAX << (32 - prefix)
+    };
+    let subnet = format!("{}/{}", Ipv4Addr::from(u32::from(ip) & mask), prefix);
+    Ok((interface, address, subnet))
+}
+
+fn nft_ruleset(interface: &str, subnet: &str) -> String {
+    format!(
+        r#"table inet {table} {{
+    chain forward {{
+        type filter hook forward priority filter; policy accept;
+        iifname "{iface}" accept
+        oifname "{iface}" ct state established,related accept
+    }}
+    chain postrouting {{
+        type nat hook postrouting priority srcnat; policy accept;
+        ip saddr {subnet} ip daddr != {subnet} masquerade
+    }}
+}}
+"#,
+        table = NFT_TABLE,
+        iface = interface,
+        subnet = subnet
+    )
+}
+
+/// 是否启用了旁路由（网关）模式
+pub fn is_enabled_in_settings(app: &AppHandle) -> bool {
+    app.get_store("settings.json")
+        .and_then(|store| store.get("enable_bypass_router_key"))
+        .and_then(|value| value.as_bool())
+        .unwrap_or(false)
+}
+
+fn set_forwarding(password: &str, ipv4: &str, ipv6: &str) -> Result<(), String> {
+    let output = helper::run_privileged(
+        password,
+        &format!(
+            "sysctl -w {}={} {}={}",
+            IPV4_FORWARD, ipv4, IPV6_FORWARD, ipv6
+        ),
+    )?;
+    if !output.status.success() {
+        return Err(format!(
+            "Failed to set IP forwarding: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    Ok(())
+}
+
+/// 开启 IP 转发并设置 nftables 转发与伪装规则
+pub fn enable(app: &AppHandle, password: &str) -> Result<(), String> {
+    let path = state_path(app)?;
+    let (interface, address, subnet) = lan_interface()?;
+
+    // 上次异常退出时保留的是最初的状态，不能被覆盖
+    let state = match fs::read_to_string(&path)
+        .ok()
+        .and_then(|c| serde_json::from_str::<GatewayState>(&c).ok())
+    {
+        Some(previous) => GatewayState {
+            interface: interface.clone(),
+            address: address.clone(),
+            subnet: subnet.clone(),
+            ..previous
+        },
+        None => GatewayState {
+            ipv4_forward: read_sysctl(IPV4_FORWARD),
+            ipv6_forward: read_sysctl(IPV6_FORWARD),
+            interface: interface.clone(),
+            address: address.clone(),
+            subnet: subnet.clone(),
+        },
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    fs::write(
+        &path,
+        serde_json::to_string_pretty(&state).map_err(|e| e.to_string())?,
+    )
+    .map_err(|e| e.to_string())?;
+    *STATE_PATH.lock().unwrap_or_else(|e| e.into_inner()) = Some(path);
+
+    set_forwarding(password, "1", "1")?;
+
+    // nft 需要从文件读取规则，stdin 已被 sudo 密码占用；文件在 rules 离开作用域时删除
+    let rules = helper::private_temp_file(".nft", &nft_ruleset(&interface, &subnet))?;
+    let _ = helper::run_privileged(password, &format!("nft delete table inet {}", NFT_TABLE));
+    let output = helper::run_privileged(
+        password,
+        &format!("nft -f '{}'", rules.path().to_string_lossy()),
+    )?;
+    if !output.status.success() {
+        return Err(format!(
+            "Failed to apply nftables rules: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    log::info!(
+        "Gateway mode enabled on {} ({}), LAN clients should use {} as gateway",
+        interface,
+        subnet,
+        address
+    );
+    Ok(())
+}
+
+fn restore(path: &PathBuf, password: &str) -> Result<(), String> {
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let state: GatewayState = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+
+    let _ = helper::run_privileged(password, &format!("nft delete table inet {}", NFT_TABLE));
+    // 恢复失败时保留状态文件，下次停止或启动时再次尝试
+    set_forwarding(password, &state.ipv4_forward, &state.ipv6_forward)?;
+    fs::remove_file(path).map_err(|e| e.to_string())?;
+    log::info!(
+        "Gateway mode disabled, restored ip_forward={} ipv6 forwarding={}",
+        state.ipv4_forward,
+        state.ipv6_forward
+    );
+    Ok(())
+}
+
+/// 删除转发规则并恢复原来的 sysctl 配置
+pub fn disable(password: &str) -> Result<(), String> {
+    let path = STATE_PATH.lock().unwrap_or_else(|e| e.into_inner()).take();
+    match path {
+        Some(path) if path.exists() => restore(&path, password).inspect_err(|_| {
+            *STATE_PATH.lock().unwrap_or_else(|e| e.into_inner()) = Some(path.clone());
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// 启动时恢复上次异常退出遗留的网关配置
+pub async fn recover(app: &AppHandle) {
+    let Ok(path) = state_path(app) else {
+        return;
+    };
+    if !path.exists() {
+        return;
+    }
+    log::warn!("Found gateway state from previous session, restoring");
+    let password = privilege::get_privilege_password_from_keyring().await;
+    if password.is_empty() {
+        log::error!("Cannot restore gateway state without privilege password");
+        return;
+    }
+    if let Err(e) = restore(&path, &password) {
+        log::error!("Failed to restore gateway state: {}", e);
+    }
+}
+
+/// 查询网关模式状态以及局域网设备应使用的网关地址
+#[tauri::command]
+pub fn get_gateway_status(app: AppHandle) -> Result<GatewayStatus, String> {
+    let state = state_path(&app)
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|c| serde_json::from_str::<GatewayState>(&c).ok());
+
+    let (interface, address, subnet) = match &state {
+        Some(state) => (
+            state.interface.clone(),
+            state.address.clone(),
+            state.subnet.clone(),
+        ),
+        None => lan_interface()?,
+    };
+
+    Ok(GatewayStatus {
+        enabled: state.is_some(),
+        ipv4_forwarding: read_sysctl(IPV4_FORWARD) == "1",
+        ipv6_forwarding: read_sysctl(IPV6_FORWARD) == "1",
+        interface,
+        gateway_address: address,
+        subnet,
+    })
+}
@@ -0,0 +1,209 @@
+use lazy_static::lazy_static;
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use sysproxy::Sysproxy;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_store::StoreExt;
+use tokio::task::JoinHandle;
+
+use crate::vpn::{PlatformVpnProxy, ProxyConfig, VpnProxy};
+
+// 冲突处理方式：reapply 重新设置（默认），notify 仅通知前端
+const GUARD_ACTION_STORE_KEY: &str = "system_proxy_guard_action_key";
+const GUARD_ACTIONS: [&str; 2] = ["reapply", "notify"];
+const GUARD_INTERVAL: Duration = Duration::from_secs(5);
+// 内存中最多保留的冲突记录数
+const MAX_CONFLICTS: usize = 100;
+
+/// 系统代理被其他程序修改的记录
+#[derive(Clone, Debug, Serialize)]
+pub struct ProxyConflict {
+    /// Unix 时间戳（秒）
+    pub timestamp: u64,
+    pub enable: bool,
+    pub host: String,
+    pub port: u16,
+    /// 采取的处理方式：reapply / notify
+    pub action: String,
+}
+
+lazy_static! {
+    static ref GUARD_HANDLE: Mutex<Option<JoinHandle<()>>> = Mutex::new(None);
+    static ref CONFLICTS: Mutex<Vec<ProxyConflict>> = Mutex::new(Vec::new());
+    // notify 模式下已通知过的代理设置，同一设置只通知一次
+    static ref NOTIFIED: Mutex<Option<(bool, String, u16)>> = Mutex::new(None);
+}
+
+/// 未设置或无法识别时使用 reapply
+fn parse_action(value: Option<&str>) -> &'static str {
+    GUARD_ACTIONS
+        .into_iter()
+        .find(|action| Some(*action) == value)
+        .unwrap_or(GUARD_ACTIONS[0])
+}
+
+fn guard_action(app: &AppHandle) -> String {
+    let value = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get(GUARD_ACTION_STORE_KEY));
+    parse_action(value.as_ref().and_then(|v| v.as_str())).to_string()
+}
+
+/// 系统代理未开启或没有指向本程序的混合入站
+fn is_overridden(current: &Sysproxy, expected: &ProxyConfig) -> bool {
+    !(current.enable && current.host == expected.host && current.port == expected.port)
+}
+
+fn record_conflict(conflict: ProxyConflict) {
+    let mut conflicts = CONFLICTS.lock().unwrap_or_else(|e| e.into_inner());
+    conflicts.push(conflict);
+    if conflicts.len() > MAX_CONFLICTS {
+        conflicts.remove(0);
+    }
+}
+
+async fn check_once(app: &AppHandle, expected: &ProxyConfig) {
+    let current = match Sysproxy::get_system_proxy() {
+        Ok(sys) => sys,
+        Err(e) => {
+            log::error!("[guard] Failed to read system proxy: {}", e);
+            return;
+        }
+    };
+    if !is_overridden(&current, expected) {
+        *NOTIFIED.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        return;
+    }
+
+    let action = guard_action(app);
+    {
+        let mut notified = NOTIFIED.lock().unwrap_or_else(|e| e.into_inner());
+        let seen = (current.enable, current.host.clone(), current.port);
+        if action != "notify" {
+            *notified = None;
+        } else if notified.as_ref() == Some(&seen) {
+            // 仅通知时设置不会恢复，避免每次检查都重复通知
+            return;
+        } else {
+            *notified = Some(seen);
+        }
+    }
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    log::warn!(
+        "[guard] [{}] System proxy overridden: enable={}, {}:{}, action={}",
+        timestamp,
+        current.enable,
+        current.host,
+        current.port,
+        action
+    );
+
+    let conflict = ProxyConflict {
+        timestamp,
+        enable: current.enable,
+        host: current.host,
+        port: current.port,
+        action: action.clone(),
+    };
+    record_conflict(conflict.clone());
+
+    if action == "reapply" {
+        if let Err(e) = PlatformVpnProxy::set_proxy(app).await {
+            log::error!("[guard] Failed to reapply system proxy: {}", e);
+        }
+    }
+    if let Err(e) = app.emit("system-proxy-conflict", conflict) {
+        log::error!("Failed to emit system-proxy-conflict event: {}", e);
+    }
+}
+
+/// 启动系统代理守护任务，已存在时先停止旧任务
+pub fn start(app: &AppHandle) {
+    stop();
+    let app = app.clone();
+    let handle = tokio::spawn(async move {
+        let expected = ProxyConfig::default();
+        loop {
+            tokio::time::sleep(GUARD_INTERVAL).await;
+            check_once(&app, &expected).await;
+        }
+    });
+    *GUARD_HANDLE.lock().unwrap_or_else(|e| e.into_inner()) = Some(handle);
+    log::info!("[guard] System proxy guard started");
+}
+
+/// 停止系统代理守护任务
+pub fn stop() {
+    if let Some(handle) = GUARD_HANDLE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take()
+    {
+        handle.abort();
+        log::info!("[guard] System proxy guard stopped");
+    }
+}
+
+/// 获取系统代理冲突记录
+#[tauri::command]
+pub fn get_proxy_conflicts() -> Vec<ProxyConflict> {
+    CONFLICTS
+        .lock()
+        .map(|conflicts| conflicts.clone())
+        .unwrap_or_default()
+}
+
+/// 获取系统代理被修改时的处理方式
+#[tauri::command]
+pub fn get_system_proxy_guard_action(app: AppHandle) -> String {
+    guard_action(&app)
+}
+
+/// 设置系统代理被修改时的处理方式：reapply 重新设置，notify 仅通知
+#[tauri::command]
+pub fn set_system_proxy_guard_action(app: AppHandle, action: String) -> Result<(), String> {
+    if !GUARD_ACTIONS.contains(&action.as_str()) {
+        return Err(format!("Unknown guard action: {}", action));
+    }
+    let store = app.store("settings.json").map_err(|e| e.to_string())?;
+    store.set(GUARD_ACTION_STORE_KEY, serde_json::json!(action));
+    store.save().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proxy(enable: bool, host: &str, port: u16) -> Sysproxy {
+        Sysproxy {
+            enable,
+            host: host.to_string(),
+            port,
+            bypass: String::new(),
+        }
+    }
+
+    #[test]
+    fn defaults_to_reapply() {
+        assert_eq!(parse_action(None), "reapply");
+        assert_eq!(parse_action(Some("reapply")), "reapply");
+        assert_eq!(parse_action(Some("notify")), "notify");
+        assert_eq!(parse_action(Some("Notify")), "reapply");
+        assert_eq!(parse_action(Some("")), "reapply");
+    }
+
+    #[test]
+    fn detects_overridden_proxy() {
+        let expected = ProxyConfig::default();
+        assert!(!is_overridden(&proxy(true, "127.0.0.1", 6789), &expected));
+        assert!(is_overridden(&proxy(false, "127.0.0.1", 6789), &expected));
+        assert!(is_overridden(&proxy(true, "127.0.0.1", 7890), &expected));
+        assert!(is_overridden(&proxy(true, "10.0.0.2", 6789), &expected));
+    }
+}
@@ -16,3 +16,58 @@ pub fn get_sidecar_path(program: &Path) -> Result<String, anyhow::Error> {
         None => Err(anyhow::anyhow!("Failed to get the executable directory")),
     }
 }
+
+/// 通过 sudo 执行特权命令，日志中隐藏密码
+#[cfg(unix)]
+pub fn run_privileged(password: &str, command: &str) -> Result<std::process::Output, String> {
+    let full_command = format!("echo '{}' | sudo -S {}", password, command);
+    log::info!(
+        "Executing privileged command: {}",
+        full_command.replace(password, "******")
+    );
+    std::process::Command::new("sh")
+        .arg("-c")
+        .arg(full_command)
+        .output()
+        .map_err(|e| e.to_string())
+}
+
+/// 以独占方式创建随机文件名、权限 0600 的临时文件，供 root 读取，避免其他用户预先创建或替换
+#[cfg(target_os = "linux")]
+pub fn private_temp_file(suffix: &str, content: &str) -> Result<tempfile::NamedTempFile, String> {
+    use std::io::Write;
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut file = tempfile::Builder::new()
+        .prefix("onebox-")
+        .suffix(suffix)
+        .permissions(std::fs::Permissions::from_mode(0o600))
+        .tempfile()
+        .map_err(|e| format!("Failed to create temporary file: {}", e))?;
+    file.write_all(content.as_bytes())
+        .and_then(|_| file.flush())
+        .map_err(|e| format!("Failed to write temporary file: {}", e))?;
+    Ok(file)
+}
+
+/// 执行 `ip -j` 命令并解析 JSON 输出
+#[cfg(target_os = "linux")]
+pub fn ip_json(args: &[&str]) -> Result<serde_json::Value, String> {
+    let output = std::process::Command::new("ip")
+        .arg("-j")
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run ip {}: {}", args.join(" "), e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "ip {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if stdout.trim().is_empty() {
+        return Ok(serde_json::Value::Array(Vec::new()));
+    }
+    serde_json::from_str(&stdout).map_err(|e| e.to_string())
+}
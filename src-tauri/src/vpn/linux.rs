@@ -1,22 +1,24 @@
 use anyhow;
 use std::process::Command;
-use sysproxy::Sysproxy;
+use sysproxy::{Autoproxy, Sysproxy};
 use tauri::AppHandle;
 use tauri_plugin_shell::process::Command as TauriCommand;
 use tauri_plugin_shell::ShellExt;
 
+use crate::vpn::bridges;
+use crate::vpn::bypass;
+use crate::vpn::gateway;
+use crate::vpn::resolver;
+use crate::vpn::snapshot;
+use crate::vpn::tun_diagnostics;
+use crate::vpn::tun_filters;
 use crate::vpn::VpnProxy;
 
-// 默认绕过列表
-pub static DEFAULT_BYPASS: &str =
-    "localhost,127.0.0.1,192.168.0.0/16,10.0.0.0/8,172.16.0.0/12,172.29.0.0/16,::1";
-
 /// 代理配置
 #[derive(Clone)]
 pub struct ProxyConfig {
     pub host: String,
     pub port: u16,
-    pub bypass: String,
 }
 
 impl Default for ProxyConfig {
@@ -24,19 +26,19 @@ impl Default for ProxyConfig {
         Self {
             host: "127.0.0.1".to_string(),
             port: 6789,
-            bypass: DEFAULT_BYPASS.to_string(),
         }
     }
 }
 
 /// 设置系统代理
-pub async fn set_proxy(_app: &AppHandle) -> anyhow::Result<()> {
+pub async fn set_proxy(app: &AppHandle) -> anyhow::Result<()> {
     let config = ProxyConfig::default();
+    snapshot::save(app, &config.host, config.port)?;
     let sys = Sysproxy {
         enable: true,
         host: config.host.clone(),
         port: config.port.clone(),
-        bypass: config.bypass,
+        bypass: bypass::platform_bypass(app),
     };
     sys.set_system_proxy()?;
     log::info!("Proxy set to {}:{}", config.host, config.port);
@@ -44,25 +46,68 @@ pub async fn set_proxy(_app: &AppHandle) -> anyhow::Result<()> {
 }
 
 /// 取消系统代理
-pub async fn unset_proxy(_app: &AppHandle) -> anyhow::Result<()> {
-    // 清理系统代理设置
-    let mut sysproxy = Sysproxy::get_system_proxy().map_err(|e| anyhow::anyhow!(e))?;
-    sysproxy.enable = false;
+pub async fn unset_proxy(app: &AppHandle) -> anyhow::Result<()> {
+    // 优先恢复启用代理前的原始配置
+    if let Some(snapshot) = snapshot::load(app) {
+        snapshot::apply(&snapshot)?;
+        snapshot::clear(app);
+        log::info!("Proxy restored from snapshot");
+        return Ok(());
+    }
 
-    sysproxy
-        .set_system_proxy()
-        .map_err(|e| anyhow::anyhow!(e))?;
+    // 没有快照时，仅关闭仍指向 OneBox 的手动代理或 PAC，避免覆盖用户自己的代理
+    let config = ProxyConfig::default();
+    let current = snapshot::ProxySnapshot::capture()?;
+    let Some(restored) = current.without_onebox(&config.host, config.port) else {
+        log::info!("System proxy is not set by OneBox, leave it unchanged");
+        return Ok(());
+    };
+    snapshot::apply(&restored)?;
     log::info!("Proxy unset");
     Ok(())
 }
 
+/// 设置 PAC 自动代理
+pub async fn set_pac_proxy(app: &AppHandle, url: &str) -> anyhow::Result<()> {
+    let config = ProxyConfig::default();
+    snapshot::save(app, &config.host, config.port)?;
+
+    // 先关闭手动代理，避免与 PAC 同时生效
+    let mut sysproxy = Sysproxy::get_system_proxy().map_err(|e| anyhow::anyhow!(e))?;
+    if sysproxy.enable {
+        sysproxy.enable = false;
+        sysproxy
+            .set_system_proxy()
+            .map_err(|e| anyhow::anyhow!(e))?;
+    }
+
+    let auto = Autoproxy {
+        enable: true,
+        url: url.to_string(),
+    };
+    auto.set_auto_proxy().map_err(|e| anyhow::anyhow!(e))?;
+    log::info!("PAC proxy set to {}", url);
+    Ok(())
+}
+
+/// 启动或重载 TUN 前补充配置中的网桥排除与分流规则
+pub fn prepare_tun_config(app: &AppHandle, path: &str) {
+    // 容器与虚拟机网桥的流量不应被 auto_route 接管
+    if let Err(e) = bridges::inject_route_exclude(app, path) {
+        log::error!("Failed to exclude bridge subnets from TUN: {}", e);
+    }
+    if let Err(e) = tun_filters::inject(app, path) {
+        log::error!("Failed to apply TUN filter rules: {}", e);
+    }
+}
+
 /// 特权模式下启动进程
 pub fn create_privileged_command(
     app: &AppHandle,
     sidecar_path: String,
     path: String,
     password: String,
-) -> Option<TauriCommand> {
+) -> Result<Option<TauriCommand>, String> {
     let command = format!(
         r#"echo '{}' | sudo -S '{}' run -c '{}' --disable-color"#,
         password.escape_default(),
@@ -70,7 +115,33 @@ pub fn create_privileged_command(
         path.escape_default()
     );
     log::debug!("Executing command: {}", command);
-    Some(app.shell().command("sh").args(vec!["-c", &command]))
+
+    prepare_tun_config(app, &path);
+
+    // 记录启动前的路由表，便于诊断 TUN 启动后的路由变化
+    tun_diagnostics::take_snapshot(&path);
+
+    // 旁路由模式：开启 IP 转发，让局域网设备可以将本机作为网关
+    if gateway::is_enabled_in_settings(app) {
+        if let Err(e) = gateway::enable(app, &password) {
+            log::error!("Failed to enable gateway mode: {}", e);
+            // 恢复已经修改的转发配置，不启动内核
+            if let Err(e) = gateway::disable(&password) {
+                log::error!("Failed to roll back gateway mode: {}", e);
+            }
+            return Err(format!("Failed to enable gateway mode: {}", e));
+        }
+    }
+
+    // systemd-resolved 等会继续使用物理网卡的 DNS，tun 网卡就绪后将 DNS 指向 tun
+    let dns_app = app.clone();
+    let dns_password = password.clone();
+    std::thread::spawn(move || {
+        if let Err(e) = resolver::enable(&dns_app, &path, &dns_password) {
+            log::error!("Failed to point DNS at tun interface: {}", e);
+        }
+    });
+    Ok(Some(app.shell().command("sh").args(vec!["-c", &command])))
 }
 
 /// 停止TUN模式下的进程
@@ -82,19 +153,31 @@ pub fn stop_tun_process(password: &str) -> Result<(), String> {
         .arg(command)
         .output()
         .map_err(|e| e.to_string())?;
-    Ok(())
+    cleanup_tun_session(password)
+}
+
+/// 恢复 TUN 会话修改过的 DNS 与转发配置，进程意外退出时也会调用
+pub fn cleanup_tun_session(password: &str) -> Result<(), String> {
+    if let Err(e) = resolver::disable(password) {
+        log::error!("Failed to restore DNS configuration: {}", e);
+    }
+    gateway::disable(password)
 }
 
 /// Linux平台的VPN代理实现
 pub struct LinuxVpnProxy;
 
 impl VpnProxy for LinuxVpnProxy {
-    async fn set_proxy(_app: &AppHandle) -> anyhow::Result<()> {
-        set_proxy(_app).await
+    async fn set_proxy(app: &AppHandle) -> anyhow::Result<()> {
+        set_proxy(app).await
+    }
+
+    async fn unset_proxy(app: &AppHandle) -> anyhow::Result<()> {
+        unset_proxy(app).await
     }
 
-    async fn unset_proxy(_app: &AppHandle) -> anyhow::Result<()> {
-        unset_proxy(_app).await
+    async fn set_pac_proxy(app: &AppHandle, url: &str) -> anyhow::Result<()> {
+        set_pac_proxy(app, url).await
     }
 
     fn create_privileged_command(
@@ -102,7 +185,7 @@ impl VpnProxy for LinuxVpnProxy {
         sidecar_path: String,
         path: String,
         password: String,
-    ) -> Option<TauriCommand> {
+    ) -> Result<Option<TauriCommand>, String> {
         create_privileged_command(app, sidecar_path, path, password)
     }
 
@@ -1,22 +1,19 @@
+use crate::vpn::bypass;
+use crate::vpn::snapshot;
 use crate::vpn::VpnProxy;
 use anyhow;
 use std::process::Command;
-use sysproxy::Sysproxy;
+use sysproxy::{Autoproxy, Sysproxy};
 use tauri::AppHandle;
 use tauri_plugin_shell::process::Command as TauriCommand;
 use tauri_plugin_shell::ShellExt;
 use tauri_plugin_store::StoreExt;
 
-// 默认绕过列表
-pub static DEFAULT_BYPASS: &str =
-    "127.0.0.1,192.168.0.0/16,10.0.0.0/8,172.16.0.0/12,172.29.0.0/16,localhost,*.local,*.crashlytics.com,<local>";
-
 /// 代理配置
 #[derive(Clone)]
 pub struct ProxyConfig {
     pub host: String,
     pub port: u16,
-    pub bypass: String,
 }
 
 impl Default for ProxyConfig {
@@ -24,19 +21,19 @@ impl Default for ProxyConfig {
         Self {
             host: "127.0.0.1".to_string(),
             port: 6789,
-            bypass: DEFAULT_BYPASS.to_string(),
         }
     }
 }
 
 /// 设置系统代理
-pub async fn set_proxy(_app: &AppHandle) -> anyhow::Result<()> {
+pub async fn set_proxy(app: &AppHandle) -> anyhow::Result<()> {
     let config = ProxyConfig::default();
+    snapshot::save(app, &config.host, config.port)?;
     let sys = Sysproxy {
         enable: true,
         host: config.host.clone(),
         port: config.port.clone(),
-        bypass: config.bypass,
+        bypass: bypass::platform_bypass(app),
     };
     sys.set_system_proxy().map_err(|e| anyhow::anyhow!(e))?;
     log::info!("Proxy set to {}:{}", config.host, config.port);
@@ -44,15 +41,50 @@ pub async fn set_proxy(_app: &AppHandle) -> anyhow::Result<()> {
 }
 
 /// 取消系统代理
-pub async fn unset_proxy(_app: &AppHandle) -> anyhow::Result<()> {
-    // 清理系统代理设置
-    let mut sysproxy = Sysproxy::get_system_proxy().map_err(|e| anyhow::anyhow!(e))?;
-    sysproxy.enable = false;
-    sysproxy.set_system_proxy()?;
+pub async fn unset_proxy(app: &AppHandle) -> anyhow::Result<()> {
+    // 优先恢复启用代理前的原始配置
+    if let Some(snapshot) = snapshot::load(app) {
+        snapshot::apply(&snapshot)?;
+        snapshot::clear(app);
+        log::info!("Proxy restored from snapshot");
+        return Ok(());
+    }
+
+    // 没有快照时，仅关闭仍指向 OneBox 的手动代理或 PAC，避免覆盖用户自己的代理
+    let config = ProxyConfig::default();
+    let current = snapshot::ProxySnapshot::capture()?;
+    let Some(restored) = current.without_onebox(&config.host, config.port) else {
+        log::info!("System proxy is not set by OneBox, leave it unchanged");
+        return Ok(());
+    };
+    snapshot::apply(&restored)?;
     log::info!("Proxy unset");
     Ok(())
 }
 
+/// 设置 PAC 自动代理
+pub async fn set_pac_proxy(app: &AppHandle, url: &str) -> anyhow::Result<()> {
+    let config = ProxyConfig::default();
+    snapshot::save(app, &config.host, config.port)?;
+
+    // 先关闭手动代理，避免与 PAC 同时生效
+    let mut sysproxy = Sysproxy::get_system_proxy().map_err(|e| anyhow::anyhow!(e))?;
+    if sysproxy.enable {
+        sysproxy.enable = false;
+        sysproxy
+            .set_system_proxy()
+            .map_err(|e| anyhow::anyhow!(e))?;
+    }
+
+    let auto = Autoproxy {
+        enable: true,
+        url: url.to_string(),
+    };
+    auto.set_auto_proxy().map_err(|e| anyhow::anyhow!(e))?;
+    log::info!("PAC proxy set to {}", url);
+    Ok(())
+}
+
 /// 特权模式下启动进程
 pub fn create_privileged_command(
     app: &AppHandle,
@@ -132,12 +164,16 @@ pub fn stop_tun_process(password: &str) -> Result<(), String> {
 pub struct MacOSVpnProxy;
 
 impl VpnProxy for MacOSVpnProxy {
-    async fn set_proxy(_app: &AppHandle) -> anyhow::Result<()> {
-        set_proxy(_app).await
+    async fn set_proxy(app: &AppHandle) -> anyhow::Result<()> {
+        set_proxy(app).await
+    }
+
+    async fn unset_proxy(app: &AppHandle) -> anyhow::Result<()> {
+        unset_proxy(app).await
     }
 
-    async fn unset_proxy(_app: &AppHandle) -> anyhow::Result<()> {
-        unset_proxy(_app).await
+    async fn set_pac_proxy(app: &AppHandle, url: &str) -> anyhow::Result<()> {
+        set_pac_proxy(app, url).await
     }
 
     fn create_privileged_command(
@@ -145,8 +181,8 @@ impl VpnProxy for MacOSVpnProxy {
         sidecar_path: String,
         path: String,
         password: String,
-    ) -> Option<TauriCommand> {
-        create_privileged_command(app, sidecar_path, path, password)
+    ) -> Result<Option<TauriCommand>, String> {
+        Ok(create_privileged_command(app, sidecar_path, path, password))
     }
 
     fn stop_tun_process(password: &str) -> Result<(), String> {
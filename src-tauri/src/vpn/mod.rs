@@ -9,13 +9,16 @@ pub trait VpnProxy {
     /// 取消系统代理
     async fn unset_proxy(app: &AppHandle) -> anyhow::Result<()>;
 
+    /// 设置系统自动代理配置（PAC）地址
+    async fn set_pac_proxy(app: &AppHandle, url: &str) -> anyhow::Result<()>;
+
     /// 创建特权模式命令
     fn create_privileged_command(
         app: &AppHandle,
         sidecar_path: String,
         path: String,
         password: String,
-    ) -> Option<TauriCommand>;
+    ) -> Result<Option<TauriCommand>, String>;
 
     /// 停止TUN模式进程
     fn stop_tun_process(password: &str) -> Result<(), String>;
@@ -27,18 +30,37 @@ pub trait VpnProxy {
     }
 }
 
+#[cfg(target_os = "linux")]
+pub mod bridges;
+pub mod bypass;
+#[cfg(target_os = "linux")]
+pub mod gateway;
+pub mod guard;
 pub mod helper;
 #[cfg(target_os = "linux")]
 pub mod linux;
 #[cfg(target_os = "macos")]
 pub mod macos;
+#[cfg(target_os = "linux")]
+pub mod resolver;
+pub mod snapshot;
+#[cfg(target_os = "linux")]
+pub mod tun_diagnostics;
+#[cfg(target_os = "linux")]
+pub mod tun_filters;
 #[cfg(target_os = "windows")]
 pub mod windows;
 
 // 平台适配器，使用编译时平台选择
 #[cfg(target_os = "linux")]
 pub use linux::LinuxVpnProxy as PlatformVpnProxy;
+#[cfg(target_os = "linux")]
+pub use linux::ProxyConfig;
 #[cfg(target_os = "macos")]
 pub use macos::MacOSVpnProxy as PlatformVpnProxy;
+#[cfg(target_os = "macos")]
+pub use macos::ProxyConfig;
+#[cfg(target_os = "windows")]
+pub use windows::ProxyConfig;
 #[cfg(target_os = "windows")]
 pub use windows::WindowsVpnProxy as PlatformVpnProxy;
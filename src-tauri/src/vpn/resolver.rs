@@ -0,0 +1,376 @@
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::privilege;
+use crate::vpn::helper;
+
+const DNS_STATE_FILE_NAME: &str = "dns_state.json";
+const RESOLV_CONF: &str = "/etc/resolv.conf";
+// 让 NetworkManager 暂停管理 resolv.conf，否则 DHCP 或网络变化时会覆盖 TUN 的 DNS
+const NM_DROP_IN: &str = "/etc/NetworkManager/conf.d/90-onebox-dns.conf";
+const NM_DROP_IN_CONTENT: &str =
+    "# Generated by OneBox for TUN mode, will be removed on stop\n[main]\ndns=none\nrc-manager=unmanaged\n";
+// 等待 sing-box 创建 tun 网卡的最长时间
+const TUN_WAIT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// 系统使用的 DNS 解析方式
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResolverKind {
+    SystemdResolved,
+    NetworkManager,
+    ResolvConf,
+}
+
+/// 修改 DNS 前的系统状态，停止或崩溃后据此恢复
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct DnsState {
+    kind: ResolverKind,
+    interface: String,
+    /// 原 resolv.conf 为符号链接时记录链接目标
+    resolv_link: Option<String>,
+    /// 原 resolv.conf 为普通文件时记录其内容
+    resolv_content: Option<String>,
+}
+
+/// 当前会话的状态文件路径，以及每次停止时递增的代数
+#[derive(Default)]
+struct Session {
+    generation: u64,
+    state_path: Option<PathBuf>,
+}
+
+lazy_static! {
+    // enable 在修改 DNS 时持有锁，disable 会等待其完成后再恢复
+    static ref SESSION: Mutex<Session> = Mutex::new(Session::default());
+}
+
+fn generation() -> u64 {
+    SESSION.lock().unwrap_or_else(|e| e.into_inner()).generation
+}
+
+fn state_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let config_dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    Ok(config_dir.join(DNS_STATE_FILE_NAME))
+}
+
+fn command_exists(name: &str) -> bool {
+    std::process::Command::new("sh")
+        .arg("-c")
+        .arg(format!("command -v {}", name))
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+/// 检测当前系统的 DNS 解析方式
+pub fn detect() -> ResolverKind {
+    let link = fs::read_link(RESOLV_CONF)
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let content = fs::read_to_string(RESOLV_CONF).unwrap_or_default();
+
+    let resolved_stub = link.contains("systemd/resolve") || content.contains("127.0.0.53");
+    if resolved_stub && command_exists("resolvectl") {
+        return ResolverKind::SystemdResolved;
+    }
+    let nm_managed =
+        link.contains("NetworkManager") || content.contains("Generated by NetworkManager");
+    if nm_managed && command_exists("nmcli") {
+        return ResolverKind::NetworkManager;
+    }
+    ResolverKind::ResolvConf
+}
+
+/// 从配置中读取 tun 入站的 IPv4 地址，如 172.19.0.1/30
+fn tun_address(config_path: &str) -> Option<(Ipv4Addr, u32)> {
+    let content = fs::read_to_string(config_path).ok()?;
+    let config: Value = serde_json::from_str(&content).ok()?;
+    let tun = config["inbounds"]
+        .as_array()?
+        .iter()
+        .find(|inbound| inbound["type"] == "tun")?;
+    let addresses = tun["address"]
+        .as_array()
+        .or_else(|| tun["inet4_address"].as_array())
+        .cloned()
+        .or_else(|| tun["inet4_address"].as_str().map(|s| vec![Value::from(s)]))?;
+    addresses
+        .iter()
+        .filter_map(|a| a.as_str())
+        .find_map(|cidr| {
+            let (ip, prefix) = cidr.split_once('/')?;
+            Some((ip.parse().ok()?, prefix.parse().ok()?))
+        })
+}
+
+/// 查找持有指定地址的网卡名称
+fn interface_with_address(address: Ipv4Addr) -> Option<String> {
+    let links = helper::ip_json(&["-4", "addr", "show"]).ok()?;
+    links.as_array()?.iter().find_map(|link| {
+        let has_address = link["addr_info"].as_array()?.iter().any(|info| {
+            info["local"]
+                .as_str()
+                .is_some_and(|local| local == address.to_string())
+        });
+        if has_address {
+            link["ifname"].as_str().map(|s| s.to_string())
+        } else {
+            None
+        }
+    })
+}
+
+/// 根据配置中的 tun 地址查找 sing-box 创建的网卡
+pub fn find_tun_interface(config_path: &str) -> Option<String> {
+    let (address, _) = tun_address(config_path)?;
+    interface_with_address(address)
+}
+
+/// DNS 服务器使用 tun 网段内的对端地址，发往本机地址的包不会进入 tun
+fn tun_dns_server(address: Ipv4Addr, prefix: u32) -> Ipv4Addr {
+    if prefix >= 31 {
+        return address;
+    }
+    let host_mask = u32::MAX >> prefix;
+    let network = u32::from(address) & !host_mask;
+    let mut peer = u32::from(address) + 1;
+    if peer & host_mask == host_mask {
+        // 下一个地址是广播地址时使用网段内第一个地址
+        peer = network + 1;
+    }
+    Ipv4Addr::from(peer)
+}
+
+fn save_state(path: &Path, state: &DnsState) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let content = serde_json::to_string_pretty(state).map_err(|e| e.to_string())?;
+    fs::write(path, content).map_err(|e| e.to_string())
+}
+
+fn run_checked(password: &str, command: &str) -> Result<(), String> {
+    let output = helper::run_privileged(password, command)?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} failed: {}",
+            command,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    Ok(())
+}
+
+fn write_file(password: &str, target: &str, content: &str) -> Result<(), String> {
+    // sudo 的 stdin 已用于输入密码，先写临时文件再复制；文件在 tmp 离开作用域时删除
+    let tmp = helper::private_temp_file(".conf", content)?;
+    run_checked(
+        password,
+        &format!(
+            "sh -c \"rm -f {0} && cp '{1}' {0}\"",
+            target,
+            tmp.path().to_string_lossy()
+        ),
+    )
+    .map_err(|e| format!("Failed to write {}: {}", target, e))
+}
+
+fn write_resolv_conf(password: &str, content: &str) -> Result<(), String> {
+    write_file(password, RESOLV_CONF, content)
+}
+
+fn reload_network_manager(password: &str) -> Result<(), String> {
+    run_checked(password, "nmcli general reload")
+}
+
+fn apply(state: &DnsState, dns_server: Ipv4Addr, password: &str) -> Result<(), String> {
+    match state.kind {
+        ResolverKind::SystemdResolved => {
+            // 将所有域名的查询路由到 tun 网卡，物理网卡的 DNS 不再参与
+            for args in [
+                format!("dns {} {}", state.interface, dns_server),
+                format!("domain {} '~.'", state.interface),
+                format!("default-route {} true", state.interface),
+            ] {
+                let output = helper::run_privileged(password, &format!("resolvectl {}", args))?;
+                if !output.status.success() {
+                    return Err(format!(
+                        "resolvectl {} failed: {}",
+                        args,
+                        String::from_utf8_lossy(&output.stderr)
+                    ));
+                }
+            }
+            Ok(())
+        }
+        ResolverKind::NetworkManager => {
+            write_file(password, NM_DROP_IN, NM_DROP_IN_CONTENT)?;
+            reload_network_manager(password)?;
+            write_resolv_conf(password, &resolv_conf_content(dns_server))
+        }
+        ResolverKind::ResolvConf => write_resolv_conf(password, &resolv_conf_content(dns_server)),
+    }
+}
+
+fn resolv_conf_content(dns_server: Ipv4Addr) -> String {
+    format!(
+        "# Generated by OneBox for TUN mode, will be restored on stop\nnameserver {}\n",
+        dns_server
+    )
+}
+
+fn restore(path: &Path, password: &str) -> Result<(), String> {
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let state: DnsState = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+
+    match state.kind {
+        ResolverKind::SystemdResolved => {
+            // tun 网卡被删除时 resolved 会自动丢弃其配置，网卡仍存在时主动撤销
+            if interface_with_link(&state.interface) {
+                let _ = helper::run_privileged(
+                    password,
+                    &format!("resolvectl revert {}", state.interface),
+                );
+            }
+        }
+        ResolverKind::NetworkManager | ResolverKind::ResolvConf => {
+            if let Some(target) = &state.resolv_link {
+                let output = helper::run_privileged(
+                    password,
+                    &format!("ln -sf '{}' {}", target, RESOLV_CONF),
+                )?;
+                if !output.status.success() {
+                    return Err(format!(
+                        "Failed to restore {} link: {}",
+                        RESOLV_CONF,
+                        String::from_utf8_lossy(&output.stderr)
+                    ));
+                }
+            } else if let Some(content) = &state.resolv_content {
+                write_resolv_conf(password, content)?;
+            }
+            if state.kind == ResolverKind::NetworkManager {
+                // 删除配置片段后重新加载，NetworkManager 会重新生成 resolv.conf
+                run_checked(password, &format!("rm -f {}", NM_DROP_IN))?;
+                reload_network_manager(password)?;
+            }
+        }
+    }
+
+    fs::remove_file(path).map_err(|e| e.to_string())?;
+    log::info!("DNS configuration restored ({:?})", state.kind);
+    Ok(())
+}
+
+fn interface_with_link(name: &str) -> bool {
+    Path::new("/sys/class/net").join(name).exists()
+}
+
+/// 等待 tun 网卡创建后，将系统 DNS 指向 tun
+pub fn enable(app: &AppHandle, config_path: &str, password: &str) -> Result<(), String> {
+    let path = state_path(app)?;
+    let (address, prefix) =
+        tun_address(config_path).ok_or("No tun inbound address found in config")?;
+    // 等待期间内核被停止时放弃修改，否则 DNS 会指向已不存在的 tun
+    let started = generation();
+    let stopped = || format!("TUN session stopped before {} appeared", address);
+
+    let deadline = std::time::Instant::now() + TUN_WAIT_TIMEOUT;
+    let interface = loop {
+        if let Some(name) = interface_with_address(address) {
+            break name;
+        }
+        if generation() != started {
+            return Err(stopped());
+        }
+        if std::time::Instant::now() >= deadline {
+            return Err(format!("Timed out waiting for tun interface {}", address));
+        }
+        std::thread::sleep(Duration::from_millis(500));
+    };
+
+    let mut session = SESSION.lock().unwrap_or_else(|e| e.into_inner());
+    if session.generation != started {
+        return Err(stopped());
+    }
+
+    // 上次异常退出遗留的状态才是最初的配置，不能被覆盖
+    let previous = fs::read_to_string(&path)
+        .ok()
+        .and_then(|c| serde_json::from_str::<DnsState>(&c).ok());
+    let state = match previous {
+        Some(previous) => DnsState {
+            interface: interface.clone(),
+            ..previous
+        },
+        None => {
+            let kind = detect();
+            let resolv_link = fs::read_link(RESOLV_CONF)
+                .ok()
+                .map(|p| p.to_string_lossy().into_owned());
+            let resolv_content = if resolv_link.is_none() {
+                fs::read_to_string(RESOLV_CONF).ok()
+            } else {
+                None
+            };
+            DnsState {
+                kind,
+                interface: interface.clone(),
+                resolv_link,
+                resolv_content,
+            }
+        }
+    };
+    save_state(&path, &state)?;
+    session.state_path = Some(path);
+
+    let dns_server = tun_dns_server(address, prefix);
+    apply(&state, dns_server, password)?;
+    log::info!(
+        "DNS pointed at {} via {} ({:?})",
+        dns_server,
+        interface,
+        state.kind
+    );
+    Ok(())
+}
+
+/// 恢复 TUN 会话前的 DNS 配置
+pub fn disable(password: &str) -> Result<(), String> {
+    let path = {
+        let mut session = SESSION.lock().unwrap_or_else(|e| e.into_inner());
+        session.generation += 1;
+        session.state_path.take()
+    };
+    match path {
+        Some(path) if path.exists() => restore(&path, password),
+        _ => Ok(()),
+    }
+}
+
+/// 启动时恢复上次异常退出遗留的 DNS 配置
+pub async fn recover(app: &AppHandle) {
+    let Ok(path) = state_path(app) else {
+        return;
+    };
+    if !path.exists() {
+        return;
+    }
+    log::warn!("Found DNS state from previous session, restoring");
+    let password = privilege::get_privilege_password_from_keyring().await;
+    if password.is_empty() {
+        log::error!("Cannot restore DNS configuration without privilege password");
+        return;
+    }
+    if let Err(e) = restore(&path, &password) {
+        log::error!("Failed to restore DNS configuration: {}", e);
+    }
+}
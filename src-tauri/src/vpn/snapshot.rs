@@ -0,0 +1,214 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use sysproxy::{Autoproxy, Sysproxy};
+use tauri::{AppHandle, Manager};
+
+use crate::pac;
+use crate::vpn::{PlatformVpnProxy, VpnProxy};
+
+// 快照文件名，保存在 appConfigDir 下，崩溃后下次启动仍可恢复
+const SNAPSHOT_FILE_NAME: &str = "system_proxy_snapshot.json";
+
+/// 启用代理前的系统代理配置快照
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProxySnapshot {
+    pub enable: bool,
+    pub host: String,
+    pub port: u16,
+    pub bypass: String,
+    pub pac_enable: bool,
+    pub pac_url: String,
+}
+
+impl ProxySnapshot {
+    /// 读取当前的系统代理配置（手动代理 + PAC）
+    pub fn capture() -> anyhow::Result<Self> {
+        let sys = Sysproxy::get_system_proxy().map_err(|e| anyhow::anyhow!(e))?;
+        // 部分桌面环境不支持读取 PAC，读取失败时视为未启用
+        let auto = Autoproxy::get_auto_proxy().unwrap_or_else(|e| {
+            log::warn!("Failed to read auto proxy config: {}", e);
+            Autoproxy {
+                enable: false,
+                url: String::new(),
+            }
+        });
+        Ok(Self {
+            enable: sys.enable,
+            host: sys.host,
+            port: sys.port,
+            bypass: sys.bypass,
+            pac_enable: auto.enable,
+            pac_url: auto.url,
+        })
+    }
+
+    /// 是否为指向指定地址的已启用代理
+    pub fn points_to(&self, host: &str, port: u16) -> bool {
+        self.enable && self.host == host && self.port == port
+    }
+
+    /// 是否为指向 OneBox 本地 PAC 服务的已启用自动代理
+    pub fn points_to_local_pac(&self) -> bool {
+        self.pac_enable && pac::is_local_url(&self.pac_url)
+    }
+
+    /// 关闭指向 OneBox 的手动代理与 PAC，保留其他设置；都不指向 OneBox 时返回空
+    pub fn without_onebox(&self, host: &str, port: u16) -> Option<Self> {
+        let manual = self.points_to(host, port);
+        let pac = self.points_to_local_pac();
+        if !manual && !pac {
+            return None;
+        }
+        Some(Self {
+            enable: self.enable && !manual,
+            pac_enable: self.pac_enable && !pac,
+            ..self.clone()
+        })
+    }
+}
+
+fn snapshot_path(app: &AppHandle) -> anyhow::Result<PathBuf> {
+    let config_dir = app.path().app_config_dir()?;
+    Ok(config_dir.join(SNAPSHOT_FILE_NAME))
+}
+
+/// 在设置系统代理前保存快照
+///
+/// 如果磁盘上已有快照，说明上次会话未能正常恢复（例如崩溃），此时保留旧快照，
+/// 避免把 OneBox 自己的代理配置当成用户的原始配置。
+pub fn save(app: &AppHandle, host: &str, port: u16) -> anyhow::Result<()> {
+    let path = snapshot_path(app)?;
+    if path.exists() {
+        log::info!("Keeping existing proxy snapshot: {:?}", path);
+        return Ok(());
+    }
+
+    let mut snapshot = ProxySnapshot::capture()?;
+    if snapshot.points_to(host, port) {
+        // 当前配置已经指向 OneBox，没有可恢复的原始代理
+        log::warn!(
+            "System proxy already points to {}:{}, treat as disabled",
+            host,
+            port
+        );
+        snapshot.enable = false;
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(&snapshot)?)?;
+    log::info!("Proxy snapshot saved to {:?}", path);
+    Ok(())
+}
+
+/// 读取磁盘上的快照
+pub fn load(app: &AppHandle) -> Option<ProxySnapshot> {
+    let path = snapshot_path(app).ok()?;
+    let content = fs::read_to_string(&path).ok()?;
+    match serde_json::from_str(&content) {
+        Ok(snapshot) => Some(snapshot),
+        Err(e) => {
+            log::error!("Invalid proxy snapshot {:?}: {}", path, e);
+            None
+        }
+    }
+}
+
+/// 恢复完成后删除快照
+pub fn clear(app: &AppHandle) {
+    if let Ok(path) = snapshot_path(app) {
+        if path.exists() {
+            if let Err(e) = fs::remove_file(&path) {
+                log::error!("Failed to remove proxy snapshot {:?}: {}", path, e);
+            }
+        }
+    }
+}
+
+/// 是否存在未恢复的快照
+pub fn exists(app: &AppHandle) -> bool {
+    snapshot_path(app).map(|p| p.exists()).unwrap_or(false)
+}
+
+/// 启动时恢复上次异常退出遗留的系统代理
+pub async fn recover(app: &AppHandle) {
+    if !exists(app) {
+        return;
+    }
+    log::warn!("Found proxy snapshot from previous session, restoring");
+    if let Err(e) = PlatformVpnProxy::unset_proxy(app).await {
+        log::error!("Failed to restore proxy snapshot: {}", e);
+    }
+}
+
+/// 通过 sysproxy 恢复快照（Linux / macOS）
+#[cfg(not(target_os = "windows"))]
+pub fn apply(snapshot: &ProxySnapshot) -> anyhow::Result<()> {
+    let sys = Sysproxy {
+        enable: snapshot.enable,
+        host: snapshot.host.clone(),
+        port: snapshot.port,
+        bypass: snapshot.bypass.clone(),
+    };
+    let auto = Autoproxy {
+        enable: snapshot.pac_enable,
+        url: snapshot.pac_url.clone(),
+    };
+
+    // 部分平台上两者共用同一个代理模式开关，先写入未启用的一项，再写入启用的一项
+    if snapshot.pac_enable {
+        sys.set_system_proxy().map_err(|e| anyhow::anyhow!(e))?;
+        auto.set_auto_proxy().map_err(|e| anyhow::anyhow!(e))?;
+    } else {
+        auto.set_auto_proxy().map_err(|e| anyhow::anyhow!(e))?;
+        sys.set_system_proxy().map_err(|e| anyhow::anyhow!(e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn current(enable: bool, port: u16, pac_enable: bool, pac_url: &str) -> ProxySnapshot {
+        ProxySnapshot {
+            enable,
+            host: "127.0.0.1".to_string(),
+            port,
+            bypass: "localhost".to_string(),
+            pac_enable,
+            pac_url: pac_url.to_string(),
+        }
+    }
+
+    #[test]
+    fn disables_only_settings_pointing_to_onebox() {
+        // 崩溃后遗留的 PAC 地址指向已退出的本地服务
+        let stale_pac = current(false, 0, true, "http://127.0.0.1:41234/proxy.pac");
+        assert_eq!(
+            stale_pac.without_onebox("127.0.0.1", 6789),
+            Some(current(false, 0, false, "http://127.0.0.1:41234/proxy.pac"))
+        );
+
+        let both = current(true, 6789, true, "http://127.0.0.1:41234/proxy.pac");
+        let restored = both.without_onebox("127.0.0.1", 6789).unwrap();
+        assert!(!restored.enable && !restored.pac_enable);
+
+        // 用户自己的 PAC 保留
+        let user_pac = current(true, 6789, true, "http://wpad.example.com/proxy.pac");
+        assert_eq!(
+            user_pac.without_onebox("127.0.0.1", 6789),
+            Some(current(
+                false,
+                6789,
+                true,
+                "http://wpad.example.com/proxy.pac"
+            ))
+        );
+
+        let user_proxy = current(true, 7890, false, "");
+        assert_eq!(user_proxy.without_onebox("127.0.0.1", 6789), None);
+    }
+}
@@ -0,0 +1,285 @@
+use lazy_static::lazy_static;
+use serde::Serialize;
+use serde_json::Value;
+use std::fs;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::core;
+use crate::vpn::helper;
+use crate::vpn::resolver;
+
+// 可能与 TUN 抢占路由的其他 VPN 网卡前缀
+const VPN_INTERFACE_PREFIXES: [(&str, &str); 3] = [
+    ("wg", "wireguard"),
+    ("tun", "openvpn"),
+    ("tailscale", "tailscale"),
+];
+
+/// 启动 TUN 前的路由表与策略路由快照
+#[derive(Clone)]
+struct RouteSnapshot {
+    timestamp: u64,
+    config_path: String,
+    routes: Vec<Value>,
+    rules: Vec<Value>,
+}
+
+/// sing-box 创建的 tun 网卡信息
+#[derive(Clone, Debug, Serialize)]
+pub struct TunInterface {
+    pub name: String,
+    pub mtu: u64,
+    pub state: String,
+    pub flags: Vec<String>,
+    pub addresses: Vec<String>,
+}
+
+/// 可能冲突的其他 VPN 网卡
+#[derive(Clone, Debug, Serialize)]
+pub struct VpnConflict {
+    pub name: String,
+    /// wireguard / openvpn / tailscale
+    pub kind: String,
+    pub state: String,
+    /// 经由该网卡的路由数量
+    pub routes: usize,
+    /// 是否存在经由该网卡的默认路由
+    pub has_default_route: bool,
+}
+
+/// TUN 诊断结果
+#[derive(Clone, Debug, Serialize)]
+pub struct TunDiagnostics {
+    pub interface: Option<TunInterface>,
+    /// 快照时间（Unix 时间戳，秒），没有快照时为空
+    pub snapshot_timestamp: Option<u64>,
+    pub routes_added: Vec<Value>,
+    pub routes_removed: Vec<Value>,
+    pub rules_added: Vec<Value>,
+    pub rules_removed: Vec<Value>,
+    pub conflicts: Vec<VpnConflict>,
+}
+
+lazy_static! {
+    static ref SNAPSHOT: Mutex<Option<RouteSnapshot>> = Mutex::new(None);
+}
+
+fn json_list(args: &[&str]) -> Vec<Value> {
+    match helper::ip_json(args) {
+        Ok(Value::Array(items)) => items,
+        Ok(_) => Vec::new(),
+        Err(e) => {
+            log::warn!("[tun-diag] {}", e);
+            Vec::new()
+        }
+    }
+}
+
+fn current_routes() -> Vec<Value> {
+    let mut routes = json_list(&["-4", "route", "show", "table", "all"]);
+    routes.extend(json_list(&["-6", "route", "show", "table", "all"]));
+    routes
+}
+
+fn current_rules() -> Vec<Value> {
+    let mut rules = json_list(&["-4", "rule", "show"]);
+    rules.extend(json_list(&["-6", "rule", "show"]));
+    rules
+}
+
+/// 返回 after 中存在而 before 中不存在的条目
+fn diff(before: &[Value], after: &[Value]) -> Vec<Value> {
+    after
+        .iter()
+        .filter(|item| !before.contains(item))
+        .cloned()
+        .collect()
+}
+
+/// 启动 TUN 前记录路由表和策略路由
+pub fn take_snapshot(config_path: &str) {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let snapshot = RouteSnapshot {
+        timestamp,
+        config_path: config_path.to_string(),
+        routes: current_routes(),
+        rules: current_rules(),
+    };
+    log::info!(
+        "[tun-diag] Snapshot taken: {} routes, {} rules",
+        snapshot.routes.len(),
+        snapshot.rules.len()
+    );
+    *SNAPSHOT.lock().unwrap_or_else(|e| e.into_inner()) = Some(snapshot);
+}
+
+fn string_list(value: &Value) -> Vec<String> {
+    value
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| item.as_str())
+                .map(|s| s.to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn tun_interface(name: &str) -> Option<TunInterface> {
+    let links = json_list(&["addr", "show", "dev", name]);
+    let link = links.first()?;
+    let addresses = link["addr_info"]
+        .as_array()
+        .map(|infos| {
+            infos
+                .iter()
+                .filter_map(|info| {
+                    Some(format!(
+                        "{}/{}",
+                        info["local"].as_str()?,
+                        info["prefixlen"].as_u64()?
+                    ))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    Some(TunInterface {
+        name: name.to_string(),
+        mtu: link["mtu"].as_u64().unwrap_or_default(),
+        state: link["operstate"].as_str().unwrap_or("UNKNOWN").to_string(),
+        flags: string_list(&link["flags"]),
+        addresses,
+    })
+}
+
+fn conflicts(own_interface: Option<&str>, links: &[Value], routes: &[Value]) -> Vec<VpnConflict> {
+    links
+        .iter()
+        .filter_map(|link| {
+            let name = link["ifname"].as_str()?;
+            if Some(name) == own_interface {
+                return None;
+            }
+            let (_, kind) = VPN_INTERFACE_PREFIXES
+                .iter()
+                .find(|(prefix, _)| name.starts_with(prefix))?;
+            let via: Vec<&Value> = routes.iter().filter(|r| r["dev"] == name).collect();
+            Some(VpnConflict {
+                name: name.to_string(),
+                kind: kind.to_string(),
+                state: link["operstate"].as_str().unwrap_or("UNKNOWN").to_string(),
+                routes: via.len(),
+                has_default_route: via.iter().any(|r| {
+                    matches!(
+                        r["dst"].as_str(),
+                        Some("default" | "0.0.0.0/1" | "128.0.0.0/1" | "::/1" | "8000::/1")
+                    )
+                }),
+            })
+        })
+        .collect()
+}
+
+/// 读取配置中 tun 入站指定的网卡名称
+fn configured_interface_name(config_path: &str) -> Option<String> {
+    let content = fs::read_to_string(config_path).ok()?;
+    let config: Value = serde_json::from_str(&content).ok()?;
+    config["inbounds"]
+        .as_array()?
+        .iter()
+        .find(|inbound| inbound["type"] == "tun")?["interface_name"]
+        .as_str()
+        .map(|s| s.to_string())
+}
+
+fn collect() -> TunDiagnostics {
+    // 只在复制快照时持有锁，执行 ip 命令期间不阻塞 take_snapshot
+    let snapshot = SNAPSHOT.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    let routes = current_routes();
+    let rules = current_rules();
+
+    // 没有快照时使用运行中的配置，避免把自身的 tun 网卡当作冲突
+    let config_path = snapshot
+        .as_ref()
+        .map(|s| s.config_path.clone())
+        .or_else(|| core::current_session().map(|(_, path)| path));
+    let interface_name = config_path.as_deref().and_then(|path| {
+        configured_interface_name(path).or_else(|| resolver::find_tun_interface(path))
+    });
+    let interface = interface_name.as_deref().and_then(tun_interface);
+
+    let (routes_added, routes_removed, rules_added, rules_removed) = match snapshot.as_ref() {
+        Some(s) => (
+            diff(&s.routes, &routes),
+            diff(&routes, &s.routes),
+            diff(&s.rules, &rules),
+            diff(&rules, &s.rules),
+        ),
+        None => (Vec::new(), Vec::new(), Vec::new(), Vec::new()),
+    };
+
+    TunDiagnostics {
+        interface,
+        snapshot_timestamp: snapshot.as_ref().map(|s| s.timestamp),
+        routes_added,
+        routes_removed,
+        rules_added,
+        rules_removed,
+        conflicts: conflicts(
+            interface_name.as_deref(),
+            &json_list(&["link", "show"]),
+            &routes,
+        ),
+    }
+}
+
+/// 查看 TUN 网卡状态、与启动前相比的路由变化以及可能冲突的 VPN 网卡
+#[tauri::command]
+pub async fn get_tun_diagnostics() -> Result<TunDiagnostics, String> {
+    tauri::async_runtime::spawn_blocking(collect)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn links() -> Vec<Value> {
+        vec![
+            json!({"ifname": "eth0", "operstate": "UP"}),
+            json!({"ifname": "tun0", "operstate": "UNKNOWN"}),
+            json!({"ifname": "wg0", "operstate": "UNKNOWN"}),
+        ]
+    }
+
+    #[test]
+    fn skips_own_tun_interface() {
+        let routes = vec![json!({"dst": "default", "dev": "tun0"})];
+        let found = conflicts(Some("tun0"), &links(), &routes);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name, "wg0");
+        assert_eq!(found[0].kind, "wireguard");
+    }
+
+    #[test]
+    fn reports_default_routes_of_other_vpns() {
+        let routes = vec![
+            json!({"dst": "0.0.0.0/1", "dev": "wg0"}),
+            json!({"dst": "10.0.0.0/8", "dev": "wg0"}),
+            json!({"dst": "192.168.1.0/24", "dev": "tun0"}),
+        ];
+        let found = conflicts(None, &links(), &routes);
+        let names: Vec<&str> = found.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["tun0", "wg0"]);
+        assert!(!found[0].has_default_route);
+        assert_eq!(found[1].routes, 2);
+        assert!(found[1].has_default_route);
+    }
+}
@@ -0,0 +1,446 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+// TUN 按用户、按进程分流的规则
+const TUN_FILTER_STORE_KEY: &str = "tun_filter_rules_key";
+// ip rule 的 uidrange 需要 Linux 4.10 及以上
+const UID_MIN_KERNEL: (u32, u32) = (4, 10);
+// sing-box 通过 sock_diag 查找连接所属进程，需要 Linux 3.3 及以上
+const PROCESS_MIN_KERNEL: (u32, u32) = (3, 3);
+
+/// TUN 分流规则
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TunFilterRules {
+    /// 只有这些用户的流量进入 TUN
+    pub include_uid: Vec<u32>,
+    /// 这些用户的流量不进入 TUN
+    pub exclude_uid: Vec<u32>,
+    /// 按进程名直连
+    pub direct_process: Vec<String>,
+    /// 按进程名走代理
+    pub proxy_process: Vec<String>,
+}
+
+/// 当前内核对分流规则的支持情况
+#[derive(Clone, Debug, Serialize)]
+pub struct TunFilterCapabilities {
+    pub kernel: String,
+    pub uid_supported: bool,
+    pub process_supported: bool,
+}
+
+/// 本机用户
+#[derive(Clone, Debug, Serialize)]
+pub struct LocalUser {
+    pub uid: u32,
+    pub name: String,
+    pub home: String,
+    pub shell: String,
+}
+
+/// 本机运行中的进程（按进程名合并）
+#[derive(Clone, Debug, Serialize)]
+pub struct LocalProcess {
+    pub name: String,
+    pub pids: Vec<u32>,
+    pub uids: Vec<u32>,
+}
+
+fn kernel_release() -> String {
+    fs::read_to_string("/proc/sys/kernel/osrelease")
+        .map(|s| s.trim().to_string())
+        .unwrap_or_default()
+}
+
+/// 解析内核版本号，如 6.8.0-45-generic -> (6, 8)
+fn parse_kernel_version(release: &str) -> Option<(u32, u32)> {
+    let mut parts = release.split(|c: char| !c.is_ascii_digit());
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    Some((major, minor))
+}
+
+fn capabilities() -> TunFilterCapabilities {
+    capabilities_for(kernel_release())
+}
+
+/// 按内核版本判断支持的规则类型
+fn capabilities_for(kernel: String) -> TunFilterCapabilities {
+    let version = parse_kernel_version(&kernel);
+    TunFilterCapabilities {
+        uid_supported: version.is_some_and(|v| v >= UID_MIN_KERNEL),
+        process_supported: version.is_some_and(|v| v >= PROCESS_MIN_KERNEL),
+        kernel,
+    }
+}
+
+/// 校验规则，返回错误描述
+fn validate(rules: &TunFilterRules, caps: &TunFilterCapabilities) -> Result<(), String> {
+    let uses_uid = !rules.include_uid.is_empty() || !rules.exclude_uid.is_empty();
+    if uses_uid && !caps.uid_supported {
+        return Err(format!(
+            "Kernel {} does not support uid rules, Linux {}.{} or newer is required",
+            caps.kernel, UID_MIN_KERNEL.0, UID_MIN_KERNEL.1
+        ));
+    }
+    if let Some(uid) = rules
+        .include_uid
+        .iter()
+        .find(|uid| rules.exclude_uid.contains(uid))
+    {
+        return Err(format!("uid {} is both included and excluded", uid));
+    }
+
+    let uses_process = !rules.direct_process.is_empty() || !rules.proxy_process.is_empty();
+    if uses_process && !caps.process_supported {
+        return Err(format!(
+            "Kernel {} does not support process rules, Linux {}.{} or newer is required",
+            caps.kernel, PROCESS_MIN_KERNEL.0, PROCESS_MIN_KERNEL.1
+        ));
+    }
+    for name in rules.direct_process.iter().chain(&rules.proxy_process) {
+        if name.trim().is_empty() || name.contains('/') {
+            return Err(format!("Invalid process name: {:?}", name));
+        }
+    }
+    if let Some(name) = rules
+        .direct_process
+        .iter()
+        .find(|name| rules.proxy_process.contains(name))
+    {
+        return Err(format!("Process {} is both direct and proxied", name));
+    }
+    Ok(())
+}
+
+/// 读取已保存的分流规则
+pub fn load(app: &AppHandle) -> TunFilterRules {
+    app.store("settings.json")
+        .ok()
+        .and_then(|store| store.get(TUN_FILTER_STORE_KEY))
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
+}
+
+/// 将分流规则写入配置的 tun 入站与路由规则
+fn apply(config: &mut Value, rules: &TunFilterRules) {
+    if let Some(tun) = config["inbounds"]
+        .as_array_mut()
+        .and_then(|inbounds| inbounds.iter_mut().find(|i| i["type"] == "tun"))
+    {
+        if !rules.include_uid.is_empty() {
+            tun["include_uid"] = json!(rules.include_uid);
+        }
+        if !rules.exclude_uid.is_empty() {
+            tun["exclude_uid"] = json!(rules.exclude_uid);
+        }
+    }
+
+    // 直连出站的 tag 由订阅配置决定，不一定是 direct
+    let direct_tag = config["outbounds"].as_array().and_then(|outbounds| {
+        outbounds
+            .iter()
+            .find(|o| o["type"] == "direct")
+            .and_then(|o| o["tag"].as_str())
+            .map(|s| s.to_string())
+    });
+    let proxy_tag = config["route"]["final"].as_str().map(|s| s.to_string());
+    let mut process_rules = Vec::new();
+    match direct_tag {
+        Some(tag) if !rules.direct_process.is_empty() => process_rules.push(json!({
+            "process_name": rules.direct_process,
+            "outbound": tag,
+        })),
+        None if !rules.direct_process.is_empty() => {
+            log::warn!("No direct outbound in config, skip direct process rules");
+        }
+        _ => {}
+    }
+    if let (false, Some(tag)) = (rules.proxy_process.is_empty(), proxy_tag) {
+        process_rules.push(json!({
+            "process_name": rules.proxy_process,
+            "outbound": tag,
+        }));
+    }
+    if let Some(route_rules) = config["route"]["rules"].as_array_mut() {
+        // 放在 sniff、hijack-dns 等动作规则之后，其他路由规则之前
+        let position = route_rules
+            .iter()
+            .position(|rule| rule.get("outbound").is_some())
+            .unwrap_or(route_rules.len());
+        let new_rules: Vec<Value> = process_rules
+            .into_iter()
+            .filter(|rule| !route_rules.contains(rule))
+            .collect();
+        route_rules.splice(position..position, new_rules);
+    }
+}
+
+/// 读取保存的分流规则并写入配置文件
+pub fn inject(app: &AppHandle, config_path: &str) -> anyhow::Result<()> {
+    let rules = load(app);
+    if rules == TunFilterRules::default() {
+        return Ok(());
+    }
+    if let Err(e) = validate(&rules, &capabilities()) {
+        // 内核不满足要求时跳过，避免 sing-box 启动失败
+        log::error!("Skip TUN filter rules: {}", e);
+        return Ok(());
+    }
+
+    let content = fs::read_to_string(config_path)?;
+    let mut config: Value = serde_json::from_str(&content)?;
+    apply(&mut config, &rules);
+    fs::write(config_path, serde_json::to_string_pretty(&config)?)?;
+    log::info!("Applied TUN filter rules: {:?}", rules);
+    Ok(())
+}
+
+/// 获取 TUN 分流规则
+#[tauri::command]
+pub fn get_tun_filter_rules(app: AppHandle) -> TunFilterRules {
+    load(&app)
+}
+
+/// 保存 TUN 分流规则，保存前按当前内核能力校验
+#[tauri::command]
+pub fn set_tun_filter_rules(app: AppHandle, rules: TunFilterRules) -> Result<(), String> {
+    validate(&rules, &capabilities())?;
+    let store = app.store("settings.json").map_err(|e| e.to_string())?;
+    store.set(
+        TUN_FILTER_STORE_KEY,
+        serde_json::to_value(&rules).map_err(|e| e.to_string())?,
+    );
+    store.save().map_err(|e| e.to_string())
+}
+
+/// 获取当前内核对分流规则的支持情况
+#[tauri::command]
+pub fn get_tun_filter_capabilities() -> TunFilterCapabilities {
+    capabilities()
+}
+
+/// 列出本机用户（来自 /etc/passwd）
+#[tauri::command]
+pub fn list_local_users() -> Result<Vec<LocalUser>, String> {
+    let content = fs::read_to_string("/etc/passwd").map_err(|e| e.to_string())?;
+    Ok(content
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(':').collect();
+            if fields.len() < 7 {
+                return None;
+            }
+            Some(LocalUser {
+                uid: fields[2].parse().ok()?,
+                name: fields[0].to_string(),
+                home: fields[5].to_string(),
+                shell: fields[6].to_string(),
+            })
+        })
+        .collect())
+}
+
+fn process_uid(pid: &str) -> Option<u32> {
+    let status = fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("Uid:"))
+        .and_then(|uids| uids.split_whitespace().next())
+        .and_then(|uid| uid.parse().ok())
+}
+
+fn process_name(pid: &str) -> Option<String> {
+    // sing-box 按可执行文件名匹配，读不到 exe（其他用户的进程）时退回 comm
+    fs::read_link(format!("/proc/{}/exe", pid))
+        .ok()
+        .and_then(|exe| exe.file_name().map(|n| n.to_string_lossy().into_owned()))
+        .or_else(|| {
+            fs::read_to_string(format!("/proc/{}/comm", pid))
+                .ok()
+                .map(|comm| comm.trim().to_string())
+        })
+        .filter(|name| !name.is_empty())
+}
+
+/// 列出运行中的进程（来自 /proc），按进程名合并
+#[tauri::command]
+pub fn list_local_processes() -> Result<Vec<LocalProcess>, String> {
+    let mut processes: BTreeMap<String, LocalProcess> = BTreeMap::new();
+    for entry in fs::read_dir("/proc").map_err(|e| e.to_string())?.flatten() {
+        let file_name = entry.file_name();
+        let pid = file_name.to_string_lossy();
+        let Ok(pid_num) = pid.parse::<u32>() else {
+            continue;
+        };
+        let Some(name) = process_name(&pid) else {
+            continue;
+        };
+        let process = processes
+            .entry(name.clone())
+            .or_insert_with(|| LocalProcess {
+                name,
+                pids: Vec::new(),
+                uids: Vec::new(),
+            });
+        process.pids.push(pid_num);
+        if let Some(uid) = process_uid(&pid) {
+            if !process.uids.contains(&uid) {
+                process.uids.push(uid);
+            }
+        }
+    }
+    Ok(processes.into_values().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn routes_processes_to_configured_outbounds() {
+        let mut config = json!({
+            "inbounds": [{"type": "tun", "tag": "tun-in"}],
+            "outbounds": [
+                {"type": "selector", "tag": "ExitGateway"},
+                {"type": "direct", "tag": "DIRECT"},
+            ],
+            "route": {
+                "final": "ExitGateway",
+                "rules": [
+                    {"action": "sniff"},
+                    {"rule_set": "geosite-cn", "outbound": "DIRECT"},
+                ],
+            },
+        });
+        let rules = TunFilterRules {
+            exclude_uid: vec![1000],
+            direct_process: vec!["steam".to_string()],
+            proxy_process: vec!["curl".to_string()],
+            ..Default::default()
+        };
+        apply(&mut config, &rules);
+        // 重复写入不会产生重复规则
+        apply(&mut config, &rules);
+
+        assert_eq!(config["inbounds"][0]["exclude_uid"], json!([1000]));
+        assert!(config["inbounds"][0].get("include_uid").is_none());
+        assert_eq!(
+            config["route"]["rules"],
+            json!([
+                {"action": "sniff"},
+                {"process_name": ["steam"], "outbound": "DIRECT"},
+                {"process_name": ["curl"], "outbound": "ExitGateway"},
+                {"rule_set": "geosite-cn", "outbound": "DIRECT"},
+            ])
+        );
+    }
+
+    #[test]
+    fn skips_direct_rules_without_direct_outbound() {
+        let mut config = json!({
+            "outbounds": [{"type": "selector", "tag": "ExitGateway"}],
+            "route": {"final": "ExitGateway", "rules": []},
+        });
+        let rules = TunFilterRules {
+            direct_process: vec!["steam".to_string()],
+            ..Default::default()
+        };
+        apply(&mut config, &rules);
+        assert_eq!(config["route"]["rules"], json!([]));
+    }
+
+    fn caps(uid_supported: bool, process_supported: bool) -> TunFilterCapabilities {
+        TunFilterCapabilities {
+            kernel: "4.9.0".to_string(),
+            uid_supported,
+            process_supported,
+        }
+    }
+
+    #[test]
+    fn parses_kernel_release_strings() {
+        assert_eq!(parse_kernel_version("6.8.0-45-generic"), Some((6, 8)));
+        assert_eq!(
+            parse_kernel_version("5.15.153.1-microsoft-standard-WSL2"),
+            Some((5, 15))
+        );
+        assert_eq!(parse_kernel_version("4.10"), Some((4, 10)));
+        assert_eq!(parse_kernel_version("6.1-rc3"), Some((6, 1)));
+        // 4.9 低于 4.10，需按数字而不是字符串比较
+        assert!(parse_kernel_version("4.9.337").unwrap() < UID_MIN_KERNEL);
+        assert!(parse_kernel_version("4.10.0").unwrap() >= UID_MIN_KERNEL);
+    }
+
+    #[test]
+    fn rejects_malformed_kernel_release() {
+        assert_eq!(parse_kernel_version(""), None);
+        assert_eq!(parse_kernel_version("6"), None);
+        assert_eq!(parse_kernel_version("6."), None);
+        assert_eq!(parse_kernel_version("v6.8"), None);
+        assert_eq!(parse_kernel_version("6..8"), None);
+        assert_eq!(parse_kernel_version("99999999999.1"), None);
+    }
+
+    #[test]
+    fn derives_capabilities_from_kernel() {
+        let caps = capabilities_for("6.8.0-45-generic".to_string());
+        assert!(caps.uid_supported && caps.process_supported);
+        let caps = capabilities_for("4.9.337".to_string());
+        assert!(!caps.uid_supported && caps.process_supported);
+        let caps = capabilities_for("3.2.0".to_string());
+        assert!(!caps.uid_supported && !caps.process_supported);
+        // 无法识别版本时不声明支持
+        let caps = capabilities_for(String::new());
+        assert!(!caps.uid_supported && !caps.process_supported);
+    }
+
+    #[test]
+    fn validates_uid_rules() {
+        let rules = TunFilterRules {
+            include_uid: vec![1000],
+            ..Default::default()
+        };
+        assert!(validate(&rules, &caps(true, true)).is_ok());
+        assert!(validate(&rules, &caps(false, true))
+            .unwrap_err()
+            .contains("4.9.0"));
+
+        let overlap = TunFilterRules {
+            include_uid: vec![0, 1000],
+            exclude_uid: vec![1000],
+            ..Default::default()
+        };
+        assert_eq!(
+            validate(&overlap, &caps(true, true)).unwrap_err(),
+            "uid 1000 is both included and excluded"
+        );
+        // 没有 uid 规则时不检查内核版本
+        assert!(validate(&TunFilterRules::default(), &caps(false, false)).is_ok());
+    }
+
+    #[test]
+    fn validates_process_rules() {
+        let rules = |direct: &[&str], proxy: &[&str]| TunFilterRules {
+            direct_process: direct.iter().map(|s| s.to_string()).collect(),
+            proxy_process: proxy.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        };
+        assert!(validate(&rules(&["curl"], &["firefox"]), &caps(false, true)).is_ok());
+        assert!(validate(&rules(&["curl"], &[]), &caps(true, false))
+            .unwrap_err()
+            .contains("3.3"));
+        assert!(validate(&rules(&[" "], &[]), &caps(true, true)).is_err());
+        assert!(validate(&rules(&[], &["/usr/bin/curl"]), &caps(true, true)).is_err());
+        assert_eq!(
+            validate(&rules(&["curl"], &["curl"]), &caps(true, true)).unwrap_err(),
+            "Process curl is both direct and proxied"
+        );
+    }
+}
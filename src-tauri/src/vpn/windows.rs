@@ -10,10 +10,10 @@ use windows::core::PCWSTR;
 use windows::Win32::Foundation::HWND;
 use windows::Win32::UI::Shell::ShellExecuteW;
 
+use crate::vpn::bypass;
 use crate::vpn::helper;
+use crate::vpn::snapshot;
 use crate::vpn::VpnProxy;
-// 默认绕过列表
-pub static DEFAULT_BYPASS: &str = "localhost;127.*;192.168.*;10.*;172.16.*;172.17.*;172.18.*;172.19.*;172.20.*;172.21.*;172.22.*;172.23.*;172.24.*;172.25.*;172.26.*;172.27.*;172.28.*;172.29.*;172.30.*;172.31.*;<local>";
 
 /// 代理配置
 #[derive(Clone)]
@@ -34,13 +34,15 @@ impl Default for ProxyConfig {
 /// 设置系统代理
 pub async fn set_proxy(app: &AppHandle) -> anyhow::Result<()> {
     let config = ProxyConfig::default();
+    snapshot::save(app, &config.host, config.port)?;
     let address = format!("{}:{}", config.host, config.port);
+    let bypass = bypass::platform_bypass(app);
     let sidecar_path = helper::get_sidecar_path(Path::new("sysproxy"))?;
 
-    let sidecar_command =
-        app.shell()
-            .command(sidecar_path)
-            .args(["global", &address, DEFAULT_BYPASS]);
+    let sidecar_command = app
+        .shell()
+        .command(sidecar_path)
+        .args(["global", &address, &bypass]);
 
     let output = sidecar_command
         .output()
@@ -58,21 +60,86 @@ pub async fn set_proxy(app: &AppHandle) -> anyhow::Result<()> {
 
 /// 取消系统代理
 pub async fn unset_proxy(app: &AppHandle) -> anyhow::Result<()> {
+    // 优先恢复启用代理前的原始配置
+    if let Some(snapshot) = snapshot::load(app) {
+        restore_snapshot(app, &snapshot).await?;
+        snapshot::clear(app);
+        log::info!("Proxy restored from snapshot");
+        return Ok(());
+    }
+
+    // 没有快照时，仅关闭仍指向 OneBox 的手动代理或 PAC，避免覆盖用户自己的代理
+    let config = ProxyConfig::default();
+    let current = snapshot::ProxySnapshot::capture()?;
+    let Some(restored) = current.without_onebox(&config.host, config.port) else {
+        log::info!("System proxy is not set by OneBox, leave it unchanged");
+        return Ok(());
+    };
+    restore_snapshot(app, &restored).await?;
+    log::info!("Proxy unset");
+    Ok(())
+}
+
+/// 设置 PAC 自动代理
+pub async fn set_pac_proxy(app: &AppHandle, url: &str) -> anyhow::Result<()> {
+    let config = ProxyConfig::default();
+    snapshot::save(app, &config.host, config.port)?;
     let sidecar_path = helper::get_sidecar_path(Path::new("sysproxy"))?;
 
-    let sidecar_command = app.shell().command(sidecar_path).args(["set", "1"]);
+    let sidecar_command = app.shell().command(sidecar_path).args(["pac", url]);
 
     let output = sidecar_command
         .output()
         .await
-        .map_err(|e| anyhow::anyhow!("Failed to unset proxy: {}", e))?;
+        .map_err(|e| anyhow::anyhow!("Failed to set pac proxy: {}", e))?;
     if !output.status.success() {
         return Err(anyhow::anyhow!(
-            "Failed to unset proxy: {}",
+            "Failed to set pac proxy: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    log::info!("PAC proxy set to {}", url);
+    Ok(())
+}
+
+/// 通过 sysproxy 的 set 子命令恢复快照
+async fn restore_snapshot(
+    app: &AppHandle,
+    snapshot: &snapshot::ProxySnapshot,
+) -> anyhow::Result<()> {
+    // flags: 1 直连, 2 手动代理, 4 PAC
+    let mut flags = 1;
+    if snapshot.enable {
+        flags |= 2;
+    }
+    if snapshot.pac_enable {
+        flags |= 4;
+    }
+    let server = if snapshot.host.is_empty() {
+        String::new()
+    } else {
+        format!("{}:{}", snapshot.host, snapshot.port)
+    };
+    let sidecar_path = helper::get_sidecar_path(Path::new("sysproxy"))?;
+
+    let sidecar_command = app.shell().command(sidecar_path).args([
+        "set",
+        &flags.to_string(),
+        &server,
+        &snapshot.bypass,
+        &snapshot.pac_url,
+    ]);
+
+    let output = sidecar_command
+        .output()
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to restore proxy: {}", e))?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "Failed to restore proxy: {}",
             String::from_utf8_lossy(&output.stderr)
         ));
     }
-    log::info!("Proxy unset");
     Ok(())
 }
 
@@ -197,13 +264,17 @@ impl VpnProxy for WindowsVpnProxy {
         unset_proxy(app).await
     }
 
+    async fn set_pac_proxy(app: &AppHandle, url: &str) -> anyhow::Result<()> {
+        set_pac_proxy(app, url).await
+    }
+
     fn create_privileged_command(
         app: &AppHandle,
         sidecar_path: String,
         path: String,
         password: String,
-    ) -> Option<TauriCommand> {
-        create_privileged_command(app, sidecar_path, path, password)
+    ) -> Result<Option<TauriCommand>, String> {
+        Ok(create_privileged_command(app, sidecar_path, path, password))
     }
 
     fn stop_tun_process(password: &str) -> Result<(), String> {
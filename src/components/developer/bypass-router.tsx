@@ -64,7 +64,7 @@ export default function ToggleBypassRouter() {
 
     };
 
-    if (type() !== "macos") {
+    if (type() !== "macos" && type() !== "linux") {
         return null;
     }
 
@@ -1,5 +1,4 @@
 import { invoke } from "@tauri-apps/api/core";
-import { getCurrentWindow } from "@tauri-apps/api/window";
 import { confirm, message } from '@tauri-apps/plugin-dialog';
 import { useContext, useEffect, useRef, useState } from "react";
 import useSWR from "swr";
@@ -32,12 +31,8 @@ export function useNetworkCheck(key: string, checkFn: () => Promise<number>) {
 
                 if (answer) {
                     setConfirmShown(false);
-                    await vpnServiceManager.stop();
-                    let url = await invoke("get_captive_redirect_url");
-                    await invoke('open_browser', {
-                        app: getCurrentWindow(),
-                        url: url
-                    });
+                    // open_browser 会先停止代理再获取认证地址，并在认证完成后自动恢复
+                    await invoke('open_browser');
                 }
 
                 setTimeout(() => {
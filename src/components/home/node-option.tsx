@@ -1,13 +1,11 @@
-import { fetch } from '@tauri-apps/plugin-http';
+import { invoke } from '@tauri-apps/api/core';
 import { useCallback, useEffect, useMemo, useState } from 'react';
 import useSWR from "swr";
-import { getClashApiSecret } from '../../single/store';
 import { t } from '../../utils/helper';
 
 // 常量定义
 const API_CONFIG = {
-    BASE_URL: 'http://127.0.0.1:9191',
-    TIMEOUT: 3000,
+    TIMEOUT: 5000,
     REFRESH_INTERVAL: 5000,
     TIMEOUT_DELAY: 2000
 } as const;
@@ -38,33 +36,25 @@ const STYLES = {
 
 // 自定义 Hook：管理代理延迟数据
 const useProxyDelay = (nodeName: string) => {
-    const fetcher = useCallback(async (url: string): Promise<ProxyResponse> => {
+    const fetcher = useCallback(async (): Promise<ProxyResponse> => {
         if (!nodeName) {
             return { delay: '-' };
         }
 
         try {
-            const response = await fetch(url, {
-                method: 'GET',
-                headers: {
-                    "Authorization": `Bearer ${await getClashApiSecret()}`,
-                    'Content-Type': 'application/json',
-                    'Accept': 'application/json',
-                },
+            const delay = await invoke<number>('test_clash_proxy_delay', {
+                name: nodeName,
+                url: DelayTestUrl,
+                timeout: API_CONFIG.TIMEOUT,
             });
-
-            if (!response.ok) {
-                throw new Error(`HTTP ${response.status}`);
-            }
-
-            return await response.json();
+            return { delay };
         } catch (error) {
             console.warn(`Failed to fetch proxy delay for ${nodeName}:`, error);
             return { delay: '-' };
         }
     }, [nodeName]);
 
-    const swrKey = nodeName ? `${API_CONFIG.BASE_URL}/proxies/${encodeURIComponent(nodeName)}/delay?url=${encodeURIComponent(DelayTestUrl)}&timeout=5000` : null;
+    const swrKey = nodeName ? `swr-proxy-delay-${nodeName}` : null;
 
     const { data, error, isLoading } = useSWR<ProxyResponse>(
         swrKey,
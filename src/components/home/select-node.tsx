@@ -1,14 +1,11 @@
 import { useEffect, useState } from "react";
 
 import { invoke } from "@tauri-apps/api/core";
-import { fetch } from '@tauri-apps/plugin-http';
 import useSWR from "swr";
-import { getClashApiSecret } from "../../single/store";
 import { t } from "../../utils/helper";
 import NodeOption from "./node-option";
 
-const baseUrl = "http://127.0.0.1:9191";
-const proxiesUrl = `${baseUrl}/proxies/ExitGateway`;
+const EXIT_GATEWAY = "ExitGateway";
 
 
 type SelectNodeProps = {
@@ -18,26 +15,14 @@ type SelectNodeProps = {
 export default function SelectNode(props: SelectNodeProps) {
 
     const { isRunning } = props;
-    const { data, isLoading, error, mutate } = useSWR(`swr-${baseUrl}/proxies/ExitGateway-${props.isRunning}`, async () => {
+    const { data, isLoading, error, mutate } = useSWR(`swr-proxies-${EXIT_GATEWAY}-${props.isRunning}`, async () => {
         if (!isRunning) {
             return {
                 all: [],
                 now: "",
             }
         }
-        const url = `${baseUrl}/proxies/ExitGateway`;
-        const response = await fetch(url, {
-            method: 'GET',
-            // @ts-ignore
-            timeout: 3,
-            headers: {
-                'Accept': 'application/json',
-                'Content-Type': 'application/json',
-                "Authorization": `Bearer ${await getClashApiSecret()}`,
-            },
-        });
-        let res = await response.json();
-        return res
+        return invoke<{ all?: string[], now?: string }>('get_clash_proxy', { name: EXIT_GATEWAY });
     }, {
         revalidateOnFocus: true,
         refreshInterval: 1000,
@@ -122,15 +107,8 @@ export function SelecItem(props: SelecItemProps) {
 
     const handleNodeChange = async (node: string) => {
         try {
-            await fetch(proxiesUrl, {
-                method: 'PUT',
-                headers: {
-                    'Accept': 'application/json',
-                    'Content-Type': 'application/json',
-                    "Authorization": `Bearer ${await getClashApiSecret()}`,
-                },
-                body: JSON.stringify({ 'name': node }),
-            });
+            // 由后端切换并按订阅记住选择，下次启动时恢复
+            await invoke('select_clash_proxy', { group: EXIT_GATEWAY, name: node });
             onUpdate();
         } catch (error) {
             console.error("Error changing node:", error);
@@ -0,0 +1,35 @@
+import { invoke } from "@tauri-apps/api/core";
+import { useEffect, useState } from "react";
+import { ShieldCheck } from "react-bootstrap-icons";
+import { t } from "../../utils/helper";
+import { ToggleSetting } from "./common";
+
+// 系统代理被其他程序修改时：开启则自动恢复（reapply），关闭则仅提示（notify）
+export default function ToggleProxyGuard() {
+    const [toggle, setToggle] = useState(true);
+
+    useEffect(() => {
+        invoke<string>("get_system_proxy_guard_action")
+            .then(action => setToggle(action === "reapply"))
+            .catch(error => console.error("Failed to load proxy guard action:", error));
+    }, []);
+
+    const handleToggle = async () => {
+        try {
+            await invoke("set_system_proxy_guard_action", { action: toggle ? "notify" : "reapply" });
+            setToggle(!toggle);
+        } catch (error) {
+            console.error("Failed to save proxy guard action:", error);
+        }
+    };
+
+    return (
+        <ToggleSetting
+            icon={<ShieldCheck className="text-[#5856D6]" size={22} />}
+            title={t("proxy_guard")}
+            subTitle={t("proxy_guard_desc")}
+            isEnabled={toggle}
+            onToggle={handleToggle}
+        />
+    );
+}
@@ -0,0 +1,82 @@
+import { useEffect, useState } from "react";
+import { Diagram3 } from "react-bootstrap-icons";
+import { toast } from "sonner";
+import { ENABLE_TUN_STORE_KEY, PROXY_MODE_STORE_KEY } from "../../types/definition";
+import { getProxyMode, setProxyMode, store, VPN_SERVICE_MODES, VpnServiceMode } from "../../single/store";
+import { t, vpnServiceManager } from "../../utils/helper";
+
+export const MODE_LABELS: Record<VpnServiceMode, string> = {
+    SystemProxy: "mode_system_proxy",
+    Pac: "mode_pac",
+    TunProxy: "mode_tun",
+    TunWithSystemProxy: "mode_tun_system_proxy",
+};
+
+export default function ProxyModeSetting() {
+    const [mode, setMode] = useState<VpnServiceMode>('SystemProxy');
+
+    useEffect(() => {
+        const loadMode = async () => {
+            try {
+                setMode(await getProxyMode());
+            } catch (error) {
+                console.error("Failed to load proxy mode:", error);
+            }
+        };
+        loadMode();
+
+        // TUN 开关也会改变模式
+        const unlisten = Promise.all([
+            store.onKeyChange(ENABLE_TUN_STORE_KEY, loadMode),
+            store.onKeyChange(PROXY_MODE_STORE_KEY, loadMode),
+        ]);
+        return () => {
+            unlisten.then(fns => fns.forEach(fn => fn()));
+        };
+    }, []);
+
+    const handleChange = async (next: VpnServiceMode) => {
+        if (next === mode) {
+            return;
+        }
+        if (!await vpnServiceManager.is_running()) {
+            await setProxyMode(next);
+            setMode(next);
+            return;
+        }
+
+        // 与切换 TUN 相同，先停止服务释放资源，再由用户重新启动
+        toast.promise(vpnServiceManager.stop(), {
+            // 请勿操作,正在释放资源中，
+            loading: t("please_wait_releasing_resources"),
+            success: async () => {
+                await setProxyMode(next);
+                setMode(next);
+                // 释放成功
+                return t("release_success_stop_vpn");
+            },
+            error: (err) => t(err.message),
+        });
+    };
+
+    return (
+        <div className="flex items-center justify-between p-4 cursor-default transition-colors">
+            <div className="flex items-center">
+                <div className="mr-4"><Diagram3 className="text-[#5856D6]" size={22} /></div>
+                <div>
+                    <div className="text-[#1C1C1E] capitalize">{t("proxy_mode")}</div>
+                    <div className="text-xs text-[#8E8E93]">{t("proxy_mode_desc")}</div>
+                </div>
+            </div>
+            <select
+                className="select select-sm select-ghost w-auto"
+                value={mode}
+                onChange={(e) => handleChange(e.target.value as VpnServiceMode)}
+            >
+                {VPN_SERVICE_MODES.map(item => (
+                    <option key={item} value={item}>{t(MODE_LABELS[item])}</option>
+                ))}
+            </select>
+        </div>
+    );
+}
@@ -1,7 +1,8 @@
 import { useEffect, useState } from "react";
 import { Cpu, Modem } from "react-bootstrap-icons";
 import { toast } from "sonner";
-import { getEnableTun, isBypassRouterEnabled, setEnableTun } from "../../single/store";
+import { ENABLE_TUN_STORE_KEY } from "../../types/definition";
+import { getEnableTun, isBypassRouterEnabled, setEnableTun, store } from "../../single/store";
 import { t, vpnServiceManager } from "../../utils/helper";
 import { ToggleSetting } from "./common";
 
@@ -26,6 +27,12 @@ export default function ToggleTun() {
         };
 
         loadTunState();
+
+        // 代理模式选择也会改变 TUN 开关
+        const unlisten = store.onKeyChange<boolean>(ENABLE_TUN_STORE_KEY, (value) => setToggle(Boolean(value)));
+        return () => {
+            unlisten.then(fn => fn());
+        };
     }, []);
 
     const icon = useBypassRouter ? <Modem className="text-[#5856D6]" size={22} /> : <Cpu className="text-[#5856D6]" size={22} />;
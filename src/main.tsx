@@ -1,7 +1,7 @@
 import { getCurrentWindow } from "@tauri-apps/api/window";
 import React from "react";
 import ReactDOM from "react-dom/client";
-import { setupStatusListener, setupTrayIcon } from "./tray";
+import { setupNetworkProfileListener, setupProxyConflictListener, setupStatusListener, setupTrayIcon } from "./tray";
 import WindowManger from './window-manger';
 
 
@@ -10,6 +10,8 @@ const appWindow = getCurrentWindow();
 if (appWindow.label === "main") {
   setupTrayIcon();
   setupStatusListener();
+  setupNetworkProfileListener();
+  setupProxyConflictListener();
 }
 
 
@@ -2,6 +2,8 @@ import AboutItem from '../components/settings/about';
 import ToggleAutoStart from '../components/settings/auto-start';
 import ToggleLan from '../components/settings/lan';
 import ToggleLanguage from '../components/settings/language';
+import ToggleProxyGuard from '../components/settings/proxy-guard';
+import ProxyModeSetting from '../components/settings/proxy-mode';
 import RouterSettingsItem from '../components/settings/router-settings';
 import ToggleTun from '../components/settings/tun';
 import UpdaterItem from '../components/settings/updater';
@@ -28,6 +30,8 @@ export default function Settings() {
             <ToggleAutoStart />
             <ToggleLan />
             <ToggleTun />
+            <ProxyModeSetting />
+            <ToggleProxyGuard />
             <ToggleLanguage />
           </div>
         </div>
@@ -2,7 +2,7 @@ import { locale, type } from '@tauri-apps/plugin-os';
 import { LazyStore } from '@tauri-apps/plugin-store';
 import { toast } from 'sonner';
 import { configType, StageVersionType } from '../config/common';
-import { ALLOWLAN_STORE_KEY, ENABLE_BYPASS_ROUTER_STORE_KEY, ENABLE_TUN_STORE_KEY, SING_BOX_MAJOR_VERSION, SING_BOX_VERSION, STAGE_VERSION_STORE_KEY, USE_DHCP_STORE_KEY, USER_AGENT_STORE_KEY } from '../types/definition';
+import { ALLOWLAN_STORE_KEY, ENABLE_BYPASS_ROUTER_STORE_KEY, ENABLE_TUN_STORE_KEY, PROXY_MODE_STORE_KEY, SING_BOX_MAJOR_VERSION, SING_BOX_VERSION, STAGE_VERSION_STORE_KEY, USE_DHCP_STORE_KEY, USER_AGENT_STORE_KEY } from '../types/definition';
 
 const OsType = type();
 export const LANGUAGE_STORE_KEY = 'language';
@@ -45,6 +45,30 @@ export async function setEnableTun(value: boolean) {
     await store.set(ENABLE_TUN_STORE_KEY, value);
     await store.save();
 }
+
+// 与后端 ProxyMode 一致
+export type VpnServiceMode = 'SystemProxy' | 'Pac' | 'TunProxy' | 'TunWithSystemProxy';
+export const VPN_SERVICE_MODES: VpnServiceMode[] = ['SystemProxy', 'Pac', 'TunProxy', 'TunWithSystemProxy'];
+
+export function isTunMode(mode: VpnServiceMode): boolean {
+    return mode === 'TunProxy' || mode === 'TunWithSystemProxy';
+}
+
+// TUN 开关被单独切换后，保存的模式与之不符时使用对应的默认模式
+export async function getProxyMode(): Promise<VpnServiceMode> {
+    const useTun = await getEnableTun();
+    const mode = await store.get<VpnServiceMode>(PROXY_MODE_STORE_KEY);
+    if (mode && VPN_SERVICE_MODES.includes(mode) && isTunMode(mode) === useTun) {
+        return mode;
+    }
+    return useTun ? 'TunProxy' : 'SystemProxy';
+}
+
+export async function setProxyMode(mode: VpnServiceMode) {
+    await store.set(PROXY_MODE_STORE_KEY, mode);
+    await store.set(ENABLE_TUN_STORE_KEY, isTunMode(mode));
+    await store.save();
+}
 export async function getAllowLan(): Promise<boolean> {
     let b = await store.get(ALLOWLAN_STORE_KEY);
     return Boolean(b);
@@ -112,8 +136,8 @@ export async function isBypassRouterEnabled(): Promise<boolean> {
 }
 
 export async function setBypassRouterEnabled(value: boolean) {
-    if (OsType !== "macos") {
-        toast.error("旁路由模式仅 macOS 支持");
+    if (OsType !== "macos" && OsType !== "linux") {
+        toast.error("旁路由模式仅 macOS 和 Linux 支持");
         return;
     }
     await store.set(ENABLE_BYPASS_ROUTER_STORE_KEY, value);
@@ -6,21 +6,38 @@ import { TrayIcon } from '@tauri-apps/api/tray';
 import { getCurrentWindow } from '@tauri-apps/api/window';
 import { message } from '@tauri-apps/plugin-dialog';
 import { type } from '@tauri-apps/plugin-os';
-import { getClashApiSecret, getStoreValue } from './single/store';
+import { MODE_LABELS } from './components/settings/proxy-mode';
+import { getClashApiSecret, getProxyMode, getStoreValue, setProxyMode, VPN_SERVICE_MODES, VpnServiceMode } from './single/store';
 import { DEVELOPER_TOGGLE_STORE_KEY } from './types/definition';
-import { copyEnvToClipboard, initLanguage, t, vpnServiceManager } from './utils/helper';
+import { copyEnvToClipboard, getSingBoxConfigPath, initLanguage, t, vpnServiceManager } from './utils/helper';
 
 
 const appWindow = getCurrentWindow();
 
 let trayInstance: TrayIcon | null = null;
 
+// 切换代理模式，运行中时以新模式重新启动
+async function switchProxyMode(mode: VpnServiceMode, running: boolean) {
+    if (mode === await getProxyMode()) {
+        return;
+    }
+    if (!running) {
+        await setProxyMode(mode);
+        return;
+    }
+    await vpnServiceManager.stop();
+    await setProxyMode(mode);
+    await vpnServiceManager.syncConfig({});
+    await vpnServiceManager.start();
+}
+
 // 创建托盘菜单
 async function createTrayMenu() {
     // 获取当前运行状态
     await initLanguage();
     let secret = await getClashApiSecret();
     const status = await invoke<boolean>("is_running", { secret: secret });
+    const currentMode = await getProxyMode();
 
     document
         .getElementById('titlebar-minimize')
@@ -56,6 +73,22 @@ async function createTrayMenu() {
                     }
                 },
             },
+            {
+                id: 'proxy_mode',
+                text: t("proxy_mode"),
+                items: VPN_SERVICE_MODES.map(mode => ({
+                    id: `proxy_mode_${mode}`,
+                    text: t(MODE_LABELS[mode]),
+                    checked: mode === currentMode,
+                    action: async () => {
+                        await switchProxyMode(mode, status);
+                        const newMenu = await createTrayMenu();
+                        if (trayInstance) {
+                            await trayInstance.setMenu(newMenu);
+                        }
+                    },
+                })),
+            },
 
         ],
     }
@@ -172,3 +205,34 @@ export async function setupStatusListener() {
         }
     });
 }
+
+// 网络方案切换：后端已更新订阅与 TUN 设置，这里重新生成配置并以方案中的模式启动
+export async function setupNetworkProfileListener() {
+    await listen<{ profile: { name: string, mode: string } }>('network-profile-activated', async (event) => {
+        const { profile } = event.payload;
+        console.log("Network profile activated:", profile.name, profile.mode);
+        try {
+            await vpnServiceManager.syncConfig({});
+            const configPath = await getSingBoxConfigPath();
+            await invoke("start", { app: appWindow, path: configPath, mode: profile.mode });
+        } catch (error) {
+            console.error('Failed to apply network profile:', error);
+        }
+    });
+}
+
+// 系统代理被其他程序修改：reapply 模式下后端已重新设置，notify 模式下提示用户
+export async function setupProxyConflictListener() {
+    await listen<{ host: string, port: number, enable: boolean, action: string }>('system-proxy-conflict', async (event) => {
+        const conflict = event.payload;
+        console.warn("System proxy changed by another program:", conflict);
+        if (conflict.action !== 'notify') {
+            return;
+        }
+        const current = conflict.enable ? `${conflict.host}:${conflict.port}` : t('system_proxy_disabled');
+        await message(
+            `${t('system_proxy_conflict')}\n${current}`,
+            { title: t('warning'), kind: 'warning' }
+        );
+    });
+}
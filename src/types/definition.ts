@@ -21,6 +21,8 @@ export const USER_AGENT_STORE_KEY = 'user_agent_key'
 export const ALLOWLAN_STORE_KEY = 'allow_lan_key'
 // 是否启用 tun 模式
 export const ENABLE_TUN_STORE_KEY = 'enable_tun_key'
+// 代理模式，是否使用 tun 仍以 ENABLE_TUN_STORE_KEY 为准
+export const PROXY_MODE_STORE_KEY = 'proxy_mode_key'
 // 当前规则模式
 export const RULE_MODE_STORE_KEY = 'rule_mode_key'
 
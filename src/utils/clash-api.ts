@@ -1,35 +1,34 @@
 
 
+import { invoke } from '@tauri-apps/api/core';
 import { useEffect, useState } from 'react';
 import { LogEntry } from '../components/log/types';
-import { getClashApiSecret } from '../single/store';
+
+interface ClashEndpoint {
+    address: string;
+    secret: string;
+}
+
+// 日志与流量是持续推送的流式接口，地址与密钥从后端读取，与运行中的配置保持一致
+async function fetchStream(path: string) {
+    const { address, secret } = await invoke<ClashEndpoint>('get_clash_endpoint');
+    return fetch(`http://${address}${path}`, {
+        headers: {
+            'Authorization': `Bearer ${secret}`
+        }
+    });
+}
 
 // 统一封装 fetch 调用
 export const ClashService = {
     async fetchLogs() {
-        const secret = await getClashApiSecret();
-        return fetch('http://localhost:9191/logs', {
-            headers: {
-                'Authorization': `Bearer ${secret}`
-            }
-        });
+        return fetchStream('/logs');
     },
     async fetchTraffic() {
-        const secret = await getClashApiSecret();
-        return fetch('http://localhost:9191/traffic', {
-            headers: {
-                'Authorization': `Bearer ${secret}`
-            }
-        });
+        return fetchStream('/traffic');
     },
     async deleteConnections() {
-        const secret = await getClashApiSecret();
-        return fetch('http://localhost:9191/connections', {
-            method: 'DELETE',
-            headers: {
-                'Authorization': `Bearer ${secret}`
-            }
-        });
+        return invoke('close_all_clash_connections');
     }
 };
 
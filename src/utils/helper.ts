@@ -9,7 +9,7 @@ import { message } from '@tauri-apps/plugin-dialog';
 import en from '../../lang/en.json';
 import zh from '../../lang/zh.json';
 import setGlobalTunConfig, { setGlobalMixedConfig, setMixedConfig, setTunConfig } from '../config/version_1_12/main';
-import { getClashApiSecret, getEnableTun, getLanguage, getStoreValue, getUserAgent } from '../single/store';
+import { getClashApiSecret, getEnableTun, getLanguage, getProxyMode, getStoreValue, getUserAgent } from '../single/store';
 const appWindow = getCurrentWindow();
 const enLang = en as Record<string, string>;
 const zhLang = zh as Record<string, string>;
@@ -112,8 +112,6 @@ export async function getSingBoxConfigPath() {
 }
 
 
-type vpnServiceManagerMode = 'SystemProxy' | 'TunProxy'
-
 type SyncConfigProps = {
     onError?: (error: any) => void;
     onSuccess?: () => void;
@@ -179,8 +177,7 @@ export const vpnServiceManager = {
     start: async () => {
         try {
             const configPath = await getSingBoxConfigPath();
-            const tunMode: boolean | undefined = await getEnableTun();
-            let mode: vpnServiceManagerMode = tunMode ? 'TunProxy' : 'SystemProxy';
+            const mode = await getProxyMode();
             console.log("启动VPN服务");
             console.log("模式:", mode);
             console.log("配置文件路径:", configPath);